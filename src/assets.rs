@@ -0,0 +1,87 @@
+use crate::error::{Error, Result};
+use crate::skill::{Profession, Resource};
+use raster::{editor, ResizeMode};
+use rusttype::{Font, FontCollection};
+use std::collections::HashMap;
+
+/// One of each kind of resource, so we know which icons to load. The values don't matter.
+const RESOURCES: [Resource; 7] = [
+	Resource::Recharge(0),
+	Resource::Cast(0.0),
+	Resource::Energy(0),
+	Resource::Adrenaline(0),
+	Resource::Overcast(0),
+	Resource::Upkeep(0),
+	Resource::Sacrifice(0),
+];
+
+/// The frame, icons and font every card is made of.
+/// Load them once and share them between all the cards you render.
+pub struct CardAssets {
+	font: Font<'static>,
+	textboxes: raster::Image,
+	profession_icons: HashMap<Profession, raster::Image>,
+	resource_icons: HashMap<String, raster::Image>,
+}
+
+impl CardAssets {
+	pub fn load() -> Result<Self> {
+		let mut profession_icons = HashMap::new();
+		for profession in Profession::iter() {
+			let mut icon = raster::open(&profession_icon_path(profession))?;
+			editor::resize(&mut icon, 110, 110, ResizeMode::Exact)?;
+			profession_icons.insert(profession, icon);
+		}
+
+		let mut resource_icons = HashMap::new();
+		for resource in RESOURCES.iter() {
+			let path = resource.icon_path();
+			let icon = raster::open(&path)?;
+			resource_icons.insert(path, icon);
+		}
+
+		Ok(Self {
+			font: load_font()?,
+			textboxes: raster::open("assets/card_frames/Textboxes.png")?,
+			profession_icons,
+			resource_icons,
+		})
+	}
+
+	pub(crate) fn font(&self) -> &Font<'static> {
+		&self.font
+	}
+
+	pub(crate) fn textboxes(&self) -> &raster::Image {
+		&self.textboxes
+	}
+
+	/// Already resized to fit the card.
+	pub(crate) fn profession_icon(&self, profession: Profession) -> &raster::Image {
+		&self.profession_icons[&profession]
+	}
+
+	pub(crate) fn resource_icon(&self, resource: &Resource) -> &raster::Image {
+		&self.resource_icons[&resource.icon_path()]
+	}
+}
+
+fn profession_icon_path(profession: Profession) -> String {
+	format!(
+		"assets/icons/{}-tango-icon-200.png",
+		if profession == Profession::Common {
+			"Any".to_owned()
+		} else {
+			profession.to_string()
+		}
+	)
+}
+
+fn load_font() -> Result<Font<'static>> {
+	let font_data = include_bytes!("../assets/fonts/Roboto-Regular.ttf") as &'static [u8];
+	let font: Font<'static> = FontCollection::from_bytes(font_data)
+		.and_then(|collection| collection.into_font())
+		.map_err(|err| Error::Font(err.to_string()))?;
+
+	Ok(font)
+}
@@ -0,0 +1,188 @@
+use crate::assets::CardAssets;
+use crate::card::{self, calc_line_width};
+use crate::error::{Error, Result};
+use crate::skill::{Campaign, Profession, Skill};
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_circle_mut, draw_filled_rect_mut, draw_text_mut};
+use imageproc::rect::Rect;
+use rusttype::{Font, Scale};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BACKGROUND: Rgba<u8> = Rgba([0x1A, 0x1A, 0x1A, 0xFF]);
+const GOLD: Rgba<u8> = Rgba([0xC8, 0xA0, 0x50, 0xFF]);
+/// Width of the gold frame around the back.
+const BORDER: u32 = 10;
+
+/// What the backs of our cards look like.
+///
+/// Read from a TOML file like this one, for backs that show the deck's profession:
+///
+/// ```toml
+/// style = "profession"
+/// ```
+///
+/// Or like this one, for a picture of your own on every back:
+///
+/// ```toml
+/// image = "my back.png"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackDesign {
+	#[serde(default)]
+	pub style: BackStyle,
+	/// A picture to use for every back instead, stretched to the size of a card.
+	/// Relative to the TOML file the design was read from.
+	pub image: Option<PathBuf>,
+}
+
+/// What a rendered back shows, on top of the "Guild Wars" title.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackStyle {
+	/// Nothing else, the same back for every deck.
+	#[default]
+	Plain,
+	/// The profession of the deck, if all its skills share one.
+	Profession,
+	/// The campaign of the deck, if all its skills come from the same one.
+	Campaign,
+}
+
+/// The back a deck ends up with.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Back {
+	Plain,
+	Profession(Profession),
+	Campaign(Campaign),
+	/// The picture from [`BackDesign::image`].
+	Custom,
+}
+
+impl Back {
+	/// The file the back is saved to in the decks directory.
+	pub fn file_name(self) -> String {
+		match self {
+			Self::Plain => "Back.png".to_owned(),
+			Self::Custom => "Custom Back.png".to_owned(),
+			Self::Profession(profession) => format!("{} Back.png", profession),
+			Self::Campaign(campaign) => format!("{} Back.png", campaign),
+		}
+	}
+}
+
+impl BackDesign {
+	pub fn load(path: &Path) -> Result<Self> {
+		let mut design: Self = toml::from_str(&fs::read_to_string(path)?)?;
+		if let (Some(image), Some(dir)) = (&design.image, path.parent()) {
+			design.image = Some(dir.join(image));
+		}
+		Ok(design)
+	}
+
+	/// The back for a deck of `skills`. Decks that mix professions or campaigns
+	/// get the plain back, even if the style asks for something else.
+	pub fn back_for(&self, skills: &[&Skill]) -> Back {
+		if self.image.is_some() {
+			return Back::Custom;
+		}
+		let shared = |key: &dyn Fn(&Skill) -> Back| {
+			let first = key(skills.first()?);
+			skills
+				.iter()
+				.all(|skill| key(skill) == first)
+				.then_some(first)
+		};
+		let back = match self.style {
+			BackStyle::Plain => None,
+			BackStyle::Profession => shared(&|skill| Back::Profession(skill.profession)),
+			BackStyle::Campaign => shared(&|skill| Back::Campaign(skill.campaign())),
+		};
+		back.unwrap_or(Back::Plain)
+	}
+
+	/// Renders `back` in memory, at the size of a card.
+	pub fn render(&self, back: Back, assets: &CardAssets) -> Result<RgbaImage> {
+		if let (Back::Custom, Some(path)) = (back, &self.image) {
+			let image = image::open(path).map_err(|err| {
+				Error::Image(format!("Can't open card back {}: {}", path.display(), err))
+			})?;
+			return Ok(imageops::resize(
+				&image.into_rgba8(),
+				card::WIDTH,
+				card::HEIGHT,
+				FilterType::Lanczos3,
+			));
+		}
+
+		let mut image = RgbaImage::from_pixel(card::WIDTH, card::HEIGHT, GOLD);
+		draw_filled_rect_mut(
+			&mut image,
+			Rect::at(BORDER as i32, BORDER as i32)
+				.of_size(card::WIDTH - 2 * BORDER, card::HEIGHT - 2 * BORDER),
+			BACKGROUND,
+		);
+		let font = assets.font();
+		let center_y = card::HEIGHT as i32 / 2;
+		match back {
+			Back::Plain | Back::Custom => {
+				draw_centered(&mut image, "Guild Wars", center_y - 40, 48.0, font);
+				draw_centered(&mut image, "Skill Cards", center_y + 20, 28.0, font);
+			}
+			Back::Profession(profession) => {
+				draw_centered(&mut image, "Guild Wars", 40, 40.0, font);
+				let icon = assets.profession_icon(profession);
+				let icon =
+					RgbaImage::from_raw(icon.width as u32, icon.height as u32, icon.bytes.clone())
+						.ok_or_else(|| {
+							Error::Image("Profession icon has the wrong size!".to_owned())
+						})?;
+				let icon = imageops::resize(&icon, 180, 180, FilterType::Lanczos3);
+				let x = (card::WIDTH - icon.width()) / 2;
+				imageops::overlay(&mut image, &icon, x, center_y as u32 - 100);
+				draw_centered(
+					&mut image,
+					&profession.to_string(),
+					center_y + 110,
+					32.0,
+					font,
+				);
+			}
+			Back::Campaign(campaign) => {
+				draw_centered(&mut image, "Guild Wars", 40, 40.0, font);
+				if let Some(glyph) = campaign.glyph() {
+					let center = (card::WIDTH as i32 / 2, center_y);
+					draw_filled_circle_mut(&mut image, center, 70, GOLD);
+					draw_filled_circle_mut(&mut image, center, 64, BACKGROUND);
+					draw_centered(&mut image, glyph, center_y - 45, 90.0, font);
+				}
+				draw_centered(
+					&mut image,
+					&campaign.to_string(),
+					center_y + 110,
+					30.0,
+					font,
+				);
+			}
+		}
+		Ok(image)
+	}
+
+	/// Renders `back` and saves it to the decks directory `dir`, returning where.
+	pub fn save(&self, back: Back, dir: &Path, assets: &CardAssets) -> Result<PathBuf> {
+		let path = dir.join(back.file_name());
+		self.render(back, assets)?.save(&path)?;
+		Ok(path)
+	}
+}
+
+/// Draws a gold line of `text` centered on the card, with its top at `y`.
+fn draw_centered(image: &mut RgbaImage, text: &str, y: i32, scale: f32, font: &Font) {
+	let scale = Scale::uniform(scale);
+	let width = calc_line_width(text, font, scale);
+	let x = (card::WIDTH as i32 - width) / 2;
+	draw_text_mut(image, GOLD, x as u32, y as u32, scale, font, text);
+}
@@ -1,78 +1,115 @@
+use crate::assets::CardAssets;
+use crate::error::{Error, Report, Result};
+use crate::io::Directories;
 use crate::skill;
-use image::{ImageBuffer, Rgba};
-use imageproc::drawing::draw_text_mut;
+use image::{ImageBuffer, Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_circle_mut, draw_text_mut};
 use raster::{editor, BlendMode, PositionMode, ResizeMode};
-use rusttype::{Font, FontCollection, Point, Scale};
-use std::{cmp, fs};
+use rayon::prelude::*;
+use rusttype::{Font, Point, Scale};
+use std::cmp;
+use std::path::Path;
+
+/// Width of a rendered card in pixels.
+pub const WIDTH: u32 = 300;
+/// Height of a rendered card in pixels.
+pub const HEIGHT: u32 = 432;
+
+/// Renders the cards of all `skills` on all cores.
+/// Skills whose card we couldn't render are recorded in `report`.
+pub fn generate_cards(
+	skills: &[skill::Skill],
+	dirs: &Directories,
+	force: bool,
+	report: &mut Report,
+) -> Result<()> {
+	let assets = CardAssets::load()?;
+
+	let failures: Vec<(&str, Error)> = skills
+		.par_iter()
+		.filter_map(|skill| {
+			generate_card(skill, dirs, &assets, force)
+				.err()
+				.map(|err| (&*skill.name, err))
+		})
+		.collect();
+	for (skill, err) in failures {
+		report.record(skill, err);
+	}
+	Ok(())
+}
 
-pub fn generate_card(skill: &skill::Skill) {
-	let path = skill.card_path();
-	if fs::metadata(&path).is_ok() {
+/// Renders the card for `skill` to `dirs.card_path(skill)`, unless it's already there.
+pub fn generate_card(
+	skill: &skill::Skill,
+	dirs: &Directories,
+	assets: &CardAssets,
+	force: bool,
+) -> Result<()> {
+	let path = dirs.card_path(skill);
+	if !force && path.exists() {
 		// Already exist
-		return;
+		return Ok(());
 	}
 
+	render_card(skill, &dirs.icon_path(skill), assets)?.save(&path)?;
+	Ok(())
+}
+
+/// Renders the card for `skill` in memory, using the skill icon at `icon_path`.
+pub fn render_card(
+	skill: &skill::Skill,
+	icon_path: &Path,
+	assets: &CardAssets,
+) -> Result<RgbaImage> {
 	let background = gen_background(skill);
 
-	let card = add_skill_image(&background, skill);
-	let card = add_textboxes(&card);
-	let card = add_profession_icon(&card, skill.profession);
-	let card = add_resource_icons(&card, &skill.resources);
+	let card = add_skill_image(&background, icon_path)?;
+	let card = add_textboxes(&card, assets)?;
+	let card = add_profession_icon(&card, assets.profession_icon(skill.profession))?;
+	let card = add_resource_icons(&card, &skill.resources, assets)?;
 
 	let mut writable_card =
-		ImageBuffer::from_raw(card.width as u32, card.height as u32, card.bytes).unwrap();
-
-	let font = load_font();
-	draw_title(&mut writable_card, &*skill.name, &font);
-	draw_type_line(&mut writable_card, &*skill.type_line(), &font);
-	draw_description(&mut writable_card, &*skill.description, &font);
-	draw_resources(&mut writable_card, &skill.resources, &font);
+		ImageBuffer::from_raw(card.width as u32, card.height as u32, card.bytes)
+			.ok_or_else(|| Error::Image("Card buffer has the wrong size!".to_owned()))?;
+
+	let font = assets.font();
+	draw_title(&mut writable_card, &skill.name, font);
+	draw_type_line(&mut writable_card, &skill.type_line(), font);
+	draw_description(&mut writable_card, &skill.description, font)?;
+	draw_resources(&mut writable_card, &skill.resources, font);
+	if let Some(glyph) = skill.campaign().glyph() {
+		draw_campaign_glyph(&mut writable_card, glyph, font);
+	}
 
-	writable_card.save(&path).unwrap();
+	Ok(writable_card)
 }
 
 fn add_profession_icon(
 	background: &raster::Image,
-	_profession: skill::Profession,
-) -> raster::Image {
-	// TODO Cache profession icon in memory, we'll use it a lot
-	let path = format!(
-		"assets/icons/{}-tango-icon-200.png",
-		if _profession == skill::Profession::Common {
-			"Any".to_owned()
-		} else {
-			_profession.to_string()
-		}
-	);
-	let mut profession_icon = raster::open(&path).unwrap();
-	editor::resize(&mut profession_icon, 110, 110, ResizeMode::Exact).unwrap();
-
-	editor::blend(
+	profession_icon: &raster::Image,
+) -> Result<raster::Image> {
+	Ok(editor::blend(
 		background,
-		&profession_icon,
+		profession_icon,
 		BlendMode::Normal,
 		0.2,
 		PositionMode::BottomCenter,
 		0,
 		-9,
-	)
-	.unwrap()
+	)?)
 }
 
-fn add_textboxes(card: &raster::Image) -> raster::Image {
-	// TODO cache textbox image in memory, we'll use it a lot
-	let textboxes = raster::open("assets/card_frames/Textboxes.png").unwrap();
-
-	editor::blend(
+fn add_textboxes(card: &raster::Image, assets: &CardAssets) -> Result<raster::Image> {
+	Ok(editor::blend(
 		card,
-		&textboxes,
+		assets.textboxes(),
 		BlendMode::Normal,
 		1.0,
 		PositionMode::TopLeft,
 		0,
 		0,
-	)
-	.unwrap()
+	)?)
 }
 
 fn draw_title(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, text: &str, font: &Font) {
@@ -118,6 +155,26 @@ fn draw_type_line(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, text: &str, font:
 	);
 }
 
+/// Marks the top right corner of the skill image with the campaign's letter.
+fn draw_campaign_glyph(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, glyph: &str, font: &Font) {
+	let center = (WIDTH as i32 - 24, 24);
+	let scale = Scale::uniform(20.0);
+
+	let white = Rgba([0xFF_u8, 0xFF_u8, 0xFF_u8, 0xFF_u8]);
+	draw_filled_circle_mut(image, center, 15, white);
+	draw_filled_circle_mut(image, center, 13, Rgba([0x0_u8, 0x0_u8, 0x0_u8, 0xFF_u8]));
+	let width = calc_line_width(glyph, font, scale);
+	draw_text_mut(
+		image,
+		white,
+		(center.0 - width / 2) as u32,
+		(center.1 - 10) as u32,
+		scale,
+		font,
+		glyph,
+	);
+}
+
 struct LineData {
 	scale: f32,
 	y_off: f32,
@@ -138,29 +195,32 @@ fn fit_line(text: &str, font: &Font, scale: f32, line_width: i32) -> LineData {
 	LineData { scale, y_off, len }
 }
 
-fn calc_line_width(text: &str, font: &Font, scale: Scale) -> i32 {
-	font
-		.layout(text, scale, Point { x: 0.0, y: 0.0 })
-		.last()
-		.unwrap()
-		.pixel_bounding_box()
-		.unwrap()
-		.max
-		.x
+/// Where the rightmost pixel of `text` ends up, or 0 if nothing in it leaves a mark.
+pub(crate) fn calc_line_width(text: &str, font: &Font, scale: Scale) -> i32 {
+	font.layout(text, scale, Point { x: 0.0, y: 0.0 })
+		// Whitespace doesn't have a bounding box.
+		.filter_map(|g| g.pixel_bounding_box())
+		.map(|rect| rect.max.x)
+		.max()
+		.unwrap_or(0)
 }
 
-fn draw_description(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, text: &str, font: &Font) {
+fn draw_description(
+	image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>,
+	text: &str,
+	font: &Font,
+) -> Result<()> {
 	let x_off = 27;
 	let y_off = 343;
 	let scale = 13.0;
 	let max_line_width = 300 - 2 * x_off;
 
-	let description_lines = split_into_lines(text, font, max_line_width, Scale::uniform(scale));
+	let description_lines = split_into_lines(text, font, max_line_width, Scale::uniform(scale))?;
 
 	let line_height = match description_lines.len() {
 		1..=4 => 15,
 		5 => 14,
-		6 | _ => 12,
+		_ => 12,
 	};
 	for (idx, line) in description_lines.iter().enumerate() {
 		draw_text_mut(
@@ -173,11 +233,16 @@ fn draw_description(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, text: &str, font
 			line,
 		);
 	}
+	Ok(())
 }
 
-fn add_resource_icons(card: &raster::Image, resources: &[skill::Resource]) -> raster::Image {
+fn add_resource_icons(
+	card: &raster::Image,
+	resources: &[skill::Resource],
+	assets: &CardAssets,
+) -> Result<raster::Image> {
 	if resources.is_empty() {
-		return card.to_owned();
+		return Ok(card.to_owned());
 	}
 	let icon_width = 20;
 	let text_max_width = 12;
@@ -194,20 +259,18 @@ fn add_resource_icons(card: &raster::Image, resources: &[skill::Resource]) -> ra
 	for (idx, res) in resources.iter().enumerate() {
 		let x_off =
 			x_start + idx * (total_resource_width + padding_right) + text_max_width + padding_inside;
-		let icon = raster::open(&res.icon_path()).unwrap();
 		card = editor::blend(
 			&card,
-			&icon,
+			assets.resource_icon(res),
 			BlendMode::Normal,
 			1.0,
 			PositionMode::TopLeft,
 			x_off as i32,
 			y_off,
-		)
-		.unwrap();
+		)?;
 	}
 
-	card
+	Ok(card)
 }
 
 fn draw_resources(
@@ -257,14 +320,20 @@ fn draw_resource_text(
 	);
 }
 
-fn split_into_lines<'a>(text: &'a str, font: &Font, line_width: i32, scale: Scale) -> Vec<&'a str> {
+/// Breaks `text` at the spaces so that each line fits into `line_width`.
+fn split_into_lines<'a>(
+	text: &'a str,
+	font: &Font,
+	line_width: i32,
+	scale: Scale,
+) -> Result<Vec<&'a str>> {
 	let mut lines = vec![];
 
-	let mut subslice_start = 0;
-	while subslice_start < text.len() {
+	let mut rest = text;
+	while !rest.is_empty() {
 		let chars_in_line = font
-			.layout(&text[subslice_start..], scale, Point { x: 0.0, y: 0.0 })
-			.filter(|g| {
+			.layout(rest, scale, Point { x: 0.0, y: 0.0 })
+			.take_while(|g| {
 				if let Some(rect) = g.pixel_bounding_box() {
 					// Does the rightmost glyph pixel still fit into the line?
 					rect.max.x <= line_width
@@ -275,35 +344,34 @@ fn split_into_lines<'a>(text: &'a str, font: &Font, line_width: i32, scale: Scal
 				}
 			})
 			.count();
-		if subslice_start + chars_in_line == text.len() {
+		// There's a glyph for every char, but chars can take up more than one byte.
+		let end = rest
+			.char_indices()
+			.nth(chars_in_line)
+			.map_or(rest.len(), |(idx, _)| idx);
+		if end == rest.len() {
 			// All done!
-			lines.push(&text[subslice_start..]);
-			subslice_start += chars_in_line;
-		} else {
-			// Find a word or sentence break.
-			let chars: Vec<char> = text.chars().collect();
-			let mut off = 0;
-			while chars[subslice_start + chars_in_line - off] != ' ' {
-				off += 1;
-				// fails silently and likely messes up things if line has no whitespace
-			}
-			lines.push(&text[subslice_start..(subslice_start + chars_in_line - off)]);
-			// Skip that whitespace
-			subslice_start += chars_in_line - off + 1;
+			lines.push(rest);
+			break;
 		}
+		// Find a word or sentence break.
+		let split = if rest[end..].starts_with(' ') {
+			Some(end)
+		} else {
+			rest[..end].rfind(' ')
+		};
+		let split = split.ok_or_else(|| {
+			Error::Image(format!(
+				"Can't fit \"{}\" on a line of the description",
+				rest.split(' ').next().unwrap_or(rest)
+			))
+		})?;
+		lines.push(&rest[..split]);
+		// Skip that whitespace
+		rest = &rest[split + 1..];
 	}
 
-	lines
-}
-
-fn load_font() -> Font<'static> {
-	let font_data = include_bytes!("../assets/fonts/Roboto-Regular.ttf") as &'static [u8];
-	let font: Font<'static> = FontCollection::from_bytes(font_data)
-		.unwrap()
-		.into_font()
-		.unwrap();
-
-	font
+	Ok(lines)
 }
 
 fn gen_background(skill: &skill::Skill) -> raster::Image {
@@ -312,25 +380,24 @@ fn gen_background(skill: &skill::Skill) -> raster::Image {
 	let color = if skill.is_elite { elite } else { black };
 
 	raster::Image {
-		width: 6 * 50,
-		height: 6 * 72,
-		bytes: color.repeat(6 * 6 * 50 * 72),
+		width: WIDTH as i32,
+		height: HEIGHT as i32,
+		bytes: color.repeat((WIDTH * HEIGHT) as usize),
 	}
 }
 
-fn add_skill_image(background: &raster::Image, skill: &skill::Skill) -> raster::Image {
+fn add_skill_image(background: &raster::Image, icon_path: &Path) -> Result<raster::Image> {
 	// no need to cache this one, only few skills re-use icons
-	let mut skill_image = raster::open(&skill.icon_path()).unwrap();
-	editor::resize(&mut skill_image, 300, 300, ResizeMode::Exact).unwrap();
+	let mut skill_image = raster::open(&icon_path.to_string_lossy())?;
+	editor::resize(&mut skill_image, 300, 300, ResizeMode::Exact)?;
 
-	editor::blend(
-		&background,
+	Ok(editor::blend(
+		background,
 		&skill_image,
 		BlendMode::Normal,
 		1.0,
 		PositionMode::TopLeft,
 		0,
 		0,
-	)
-	.unwrap()
+	)?)
 }
@@ -0,0 +1,343 @@
+use guild_wars_skill_cards::details::MAX_RANK;
+use guild_wars_skill_cards::download::{DownloadSettings, Downloader};
+use guild_wars_skill_cards::filter::PoolFilter;
+use guild_wars_skill_cards::print::{CardSize, Paper, PrintSettings};
+use guild_wars_skill_cards::source::{DataFile, HtmlDirectory, SkillSource, Wiki, WIKI_URL};
+use guild_wars_skill_cards::tabletop::{CardOrder, Grouping};
+use guild_wars_skill_cards::{Campaign, Error, Profession, Result};
+use std::path::PathBuf;
+use std::time::Duration;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Create playing cards for all Guild Wars skills.")]
+pub struct Opt {
+	/// Where to keep downloaded skill data and icons.
+	#[structopt(long, default_value = "cache", parse(from_os_str))]
+	pub cache_dir: PathBuf,
+
+	/// Where to put rendered cards and deck sheets.
+	#[structopt(long, default_value = "cards", parse(from_os_str))]
+	pub cards_dir: PathBuf,
+
+	/// Runs every stage from fetching to deck building if omitted.
+	#[structopt(subcommand)]
+	pub command: Option<Command>,
+}
+
+#[derive(Debug, Default, StructOpt)]
+pub struct Selection {
+	/// Only use skills of this profession. Can be given multiple times.
+	#[structopt(short, long = "profession", number_of_values = 1)]
+	pub professions: Vec<Profession>,
+	/// Only use skills of this campaign. Can be given multiple times.
+	#[structopt(short, long = "campaign", number_of_values = 1)]
+	pub campaigns: Vec<Campaign>,
+	/// TOML file that says which skills go into the card pool, like a ban list.
+	#[structopt(long, parse(from_os_str))]
+	pub filter: Option<PathBuf>,
+}
+
+impl Selection {
+	pub fn professions(&self) -> Vec<Profession> {
+		if self.professions.is_empty() {
+			Profession::iter().collect()
+		} else {
+			self.professions.clone()
+		}
+	}
+
+	pub fn filter(&self) -> Result<PoolFilter> {
+		let mut filter = match &self.filter {
+			Some(path) => PoolFilter::load(path)?,
+			None => PoolFilter::default(),
+		};
+		if !self.campaigns.is_empty() {
+			let campaigns = &mut filter.include.campaigns;
+			if campaigns.is_empty() {
+				*campaigns = self.campaigns.clone();
+			} else {
+				campaigns.retain(|campaign| self.campaigns.contains(campaign));
+				if campaigns.is_empty() {
+					return Err(Error::parse(
+						"None of the campaigns in the filter were selected",
+					));
+				}
+			}
+		}
+		Ok(filter)
+	}
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DownloadOpts {
+	/// How many downloads may run at the same time. Defaults to 2.
+	#[structopt(long)]
+	pub concurrency: Option<usize>,
+	/// Least milliseconds between the start of two requests. Defaults to 500.
+	#[structopt(long)]
+	pub delay_ms: Option<u64>,
+	/// How often to try again after a server error or timeout. Defaults to 3.
+	#[structopt(long)]
+	pub retries: Option<u32>,
+}
+
+impl DownloadOpts {
+	pub fn settings(&self) -> DownloadSettings {
+		let mut settings = DownloadSettings::default();
+		if let Some(concurrency) = self.concurrency {
+			settings.concurrency = concurrency;
+		}
+		if let Some(delay_ms) = self.delay_ms {
+			settings.delay = Duration::from_millis(delay_ms);
+		}
+		if let Some(retries) = self.retries {
+			settings.retries = retries;
+		}
+		settings
+	}
+}
+
+// Where to get skill data from. That's the wiki, unless told otherwise.
+#[derive(Debug, StructOpt)]
+pub struct SourceOpts {
+	/// Use a stand-in for the wiki at this address. Icons are always downloaded from here.
+	#[structopt(long, default_value = WIKI_URL)]
+	pub wiki_url: String,
+	/// Read saved skill list pages like `List_of_warrior_skills.html` from this directory.
+	#[structopt(long, parse(from_os_str), conflicts_with = "data-file")]
+	pub html_dir: Option<PathBuf>,
+	/// Read skills from this JSON or CSV file.
+	#[structopt(long, parse(from_os_str))]
+	pub data_file: Option<PathBuf>,
+	/// Also scrape each skill's own wiki page for its full attribute progression, notes,
+	/// related skills and acquisition. That's one more request per skill.
+	#[structopt(long)]
+	pub details: bool,
+	#[structopt(flatten)]
+	pub download: DownloadOpts,
+}
+
+impl SourceOpts {
+	pub fn wiki(&self) -> Result<Wiki> {
+		let downloader = Downloader::new(self.download.settings())?;
+		Ok(Wiki::new(&self.wiki_url, downloader))
+	}
+
+	pub fn source<'a>(&self, wiki: &'a Wiki) -> Box<dyn SkillSource + 'a> {
+		if let Some(dir) = &self.html_dir {
+			Box::new(HtmlDirectory::new(dir))
+		} else if let Some(path) = &self.data_file {
+			Box::new(DataFile::new(path))
+		} else {
+			Box::new(wiki)
+		}
+	}
+}
+
+#[derive(Debug, StructOpt)]
+pub struct PrintOpts {
+	/// Paper to print on: A4, or Letter.
+	#[structopt(long, default_value = "A4")]
+	pub paper: Paper,
+	/// Size of the printed cards: poker, or tarot.
+	#[structopt(long, default_value = "poker")]
+	pub card_size: CardSize,
+	/// Millimeters of extra card around each edge, so cutting a little off doesn't leave
+	/// a white border.
+	#[structopt(long, default_value = "0")]
+	pub bleed: f64,
+	/// Millimeters along the edges of the page to leave empty, for printers that can't
+	/// print all the way to the edge.
+	#[structopt(long, default_value = "5")]
+	pub margin: f64,
+	/// Leave out the lines in the margins that show where to cut.
+	#[structopt(long)]
+	pub no_cut_marks: bool,
+	/// Follow each page of cards with a page of card backs, to print on both sides
+	/// of the paper, flipping on the long edge.
+	#[structopt(long)]
+	pub duplex: bool,
+	/// Resolution of the cards in the PDF, from 72 to 1200.
+	#[structopt(long, default_value = "300")]
+	pub dpi: u32,
+}
+
+impl PrintOpts {
+	pub fn settings(&self) -> PrintSettings {
+		PrintSettings {
+			paper: self.paper,
+			card_size: self.card_size,
+			bleed: self.bleed,
+			margin: self.margin,
+			cut_marks: !self.no_cut_marks,
+			duplex: self.duplex,
+			dpi: self.dpi,
+		}
+	}
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Command {
+	/// Download skill data and icons from the wiki.
+	Fetch {
+		#[structopt(flatten)]
+		selection: Selection,
+		/// Download again even if we already have it cached.
+		#[structopt(short, long)]
+		force: bool,
+		#[structopt(flatten)]
+		source: SourceOpts,
+	},
+	/// Fetch skill data again if it changed on the wiki, and re-render the cards of changed skills.
+	///
+	/// Run `decks` afterwards to put the new cards on the deck sheets.
+	Refresh {
+		#[structopt(flatten)]
+		selection: Selection,
+		#[structopt(flatten)]
+		source: SourceOpts,
+	},
+	/// Show which skills changed between two snapshots of skill data.
+	Diff {
+		/// A skill list as written by `fetch`, or a whole data directory.
+		#[structopt(parse(from_os_str))]
+		old: PathBuf,
+		#[structopt(parse(from_os_str))]
+		new: PathBuf,
+		/// Print the changes as JSON.
+		#[structopt(long)]
+		json: bool,
+	},
+	/// Render one card per skill.
+	Render {
+		#[structopt(flatten)]
+		selection: Selection,
+		/// Render again even if the card already exists.
+		#[structopt(short, long)]
+		force: bool,
+		/// Number of cards to render at the same time. Defaults to the number of CPU cores.
+		#[structopt(short, long)]
+		jobs: Option<usize>,
+		/// Show the numbers in descriptions at this attribute rank instead of as ranges.
+		/// Only works for skills fetched with `--details`.
+		#[structopt(long, parse(try_from_str = parse_rank))]
+		rank: Option<u8>,
+	},
+	/// Put rendered cards together into Tabletop Simulator deck sheets.
+	Decks {
+		#[structopt(flatten)]
+		selection: Selection,
+		/// How to split the skills into decks, each with sheets of their own:
+		/// all, profession, campaign, attribute, or elites for a deck of only elite skills.
+		#[structopt(long)]
+		group_by: Option<Grouping>,
+		/// Put each campaign's skills on sheets of their own, the same as `--group-by campaign`.
+		#[structopt(long, conflicts_with = "group-by")]
+		by_campaign: bool,
+		/// Order of the cards in each deck: id, or name.
+		#[structopt(long, default_value = "id")]
+		sort_by: CardOrder,
+		/// TOML file that says what the card backs look like.
+		#[structopt(long, parse(from_os_str))]
+		back: Option<PathBuf>,
+		/// TOML file with the number of cards on a sheet and their size.
+		#[structopt(long, parse(from_os_str))]
+		layout: Option<PathBuf>,
+	},
+	/// Draft booster packs from the card pool.
+	///
+	/// Real players pick a card from each pack they get, fake players discard a random one.
+	Draft {
+		#[structopt(flatten)]
+		selection: Selection,
+		/// Number of players, including fake ones.
+		#[structopt(long, default_value = "5")]
+		players: usize,
+		/// Number of players who pick their own cards. Everyone else is a fake player.
+		#[structopt(long, default_value = "1")]
+		real_players: usize,
+		/// Number of cards in each booster pack.
+		#[structopt(long, default_value = "10")]
+		pack_size: usize,
+		/// Number of packs each player opens.
+		#[structopt(long, default_value = "1")]
+		rounds: usize,
+		/// Seed for the shuffle, to deal the same packs again.
+		#[structopt(long)]
+		seed: Option<u64>,
+		/// TOML file with rules for what goes into a pack, like a limit on elite skills.
+		#[structopt(long, parse(from_os_str))]
+		pack_rules: Option<PathBuf>,
+		/// Profession of a real player, for pack rules that depend on it.
+		/// Give it once for each real player, in seating order.
+		#[structopt(long = "player-profession", number_of_values = 1)]
+		player_professions: Vec<Profession>,
+		/// Also save every player's pool to this JSON file.
+		#[structopt(long, parse(from_os_str))]
+		output: Option<PathBuf>,
+	},
+	/// Turn a build template code into a deck sheet of its skills, or skills into a template code.
+	Template {
+		/// A template code as copied from the game.
+		#[structopt(required_unless = "skills")]
+		code: Option<String>,
+		/// Make a template code from up to 8 skills instead, named like in `list`.
+		/// Add (PvP) or (Codex) for those variants.
+		#[structopt(long, conflicts_with = "code")]
+		skills: Vec<String>,
+		/// Name of the deck sheet for the template's skills.
+		#[structopt(long, default_value = "Template")]
+		name: String,
+	},
+	/// Write the skills in the card pool to files for spreadsheets and other tools.
+	Export {
+		#[structopt(flatten)]
+		selection: Selection,
+		/// Files to write, as CSV, JSON Lines or SQLite depending on the extension:
+		/// `.csv`, `.jsonl`, or `.sqlite` and `.db`.
+		#[structopt(required = true, parse(from_os_str))]
+		outputs: Vec<PathBuf>,
+	},
+	/// Lay out rendered cards on the pages of a PDF, at the real size of playing cards,
+	/// to print at home and cut out.
+	Print {
+		#[structopt(flatten)]
+		selection: Selection,
+		/// The PDF file to write.
+		#[structopt(parse(from_os_str))]
+		output: PathBuf,
+		#[structopt(flatten)]
+		settings: PrintOpts,
+		/// TOML file that says what the card backs look like.
+		#[structopt(long, parse(from_os_str))]
+		back: Option<PathBuf>,
+	},
+	/// List the skills in the card pool.
+	List {
+		#[structopt(flatten)]
+		selection: Selection,
+	},
+	/// Delete cached and generated files. Deletes everything if no flags are given.
+	Clean {
+		/// Delete the cached skill data.
+		#[structopt(long)]
+		data: bool,
+		/// Delete the cached skill icons.
+		#[structopt(long)]
+		images: bool,
+		/// Delete the rendered cards, including deck sheets.
+		#[structopt(long)]
+		cards: bool,
+		/// Delete the deck sheets.
+		#[structopt(long)]
+		decks: bool,
+	},
+}
+
+fn parse_rank(rank: &str) -> std::result::Result<u8, String> {
+	match rank.parse() {
+		Ok(rank) if rank <= MAX_RANK => Ok(rank),
+		_ => Err(format!("Attribute ranks go from 0 to {}", MAX_RANK)),
+	}
+}
@@ -0,0 +1,180 @@
+use crate::error::{Error, Result};
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::{Deserialize, Serialize};
+
+/// The highest attribute rank a character can reach, with runes, headgear and bonuses.
+pub const MAX_RANK: u8 = 21;
+
+/// What a skill's own wiki page tells us on top of the skill lists.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SkillDetails {
+	pub id: Option<u16>,
+	/// One row for each number in the description that grows with the attribute.
+	pub progression: Vec<Progression>,
+	pub related_skills: Vec<String>,
+	pub notes: Vec<String>,
+	pub acquisition: Vec<String>,
+}
+
+/// How a number in a skill's description grows with its attribute.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Progression {
+	pub label: String,
+	/// The value at every attribute rank from 0 to `MAX_RANK`.
+	pub values: Vec<f32>,
+}
+
+impl Progression {
+	pub fn at(&self, rank: u8) -> Option<f32> {
+		self.values.get(rank as usize).copied()
+	}
+}
+
+impl SkillDetails {
+	/// The progression behind a `low...high` range in the description,
+	/// which the wiki shows for ranks 0 and 15.
+	pub fn progression_for(&self, low: f32, high: f32) -> Option<&Progression> {
+		self.progression
+			.iter()
+			.find(|row| row.at(0) == Some(low) && row.at(15) == Some(high))
+	}
+}
+
+/// Parses a skill's own page on the wiki.
+pub fn parse_details(raw_html: &str) -> Result<SkillDetails> {
+	let page = Html::parse_document(raw_html);
+	let select_content = Selector::parse(".mw-parser-output").unwrap();
+	let content = page
+		.select(&select_content)
+		.next()
+		.ok_or_else(|| Error::parse("Skill page has no content!"))?;
+
+	let mut details = SkillDetails {
+		id: skill_id(content)?,
+		progression: progression(content)?,
+		..SkillDetails::default()
+	};
+
+	// Notes and such are lists below a heading, sometimes split up further by subheadings.
+	let select_items = Selector::parse("li").unwrap();
+	let select_link = Selector::parse("a[title]").unwrap();
+	let mut section = String::new();
+	for element in content.children().filter_map(ElementRef::wrap) {
+		match element.value().name() {
+			"h2" => section = headline(element),
+			"ul" => {
+				let items = element.select(&select_items);
+				match section.as_str() {
+					"Notes" => details.notes.extend(items.map(item_text)),
+					"Acquisition" => details.acquisition.extend(items.map(item_text)),
+					"Related skills" => details.related_skills.extend(
+						items.filter_map(|item| item.select(&select_link).next().map(text)),
+					),
+					_ => (),
+				}
+			}
+			_ => (),
+		}
+	}
+	Ok(details)
+}
+
+fn skill_id(content: ElementRef) -> Result<Option<u16>> {
+	let select_fields = Selector::parse(".skill-box dt, .skill-box dd").unwrap();
+	let mut fields = content.select(&select_fields);
+	while let Some(field) = fields.next() {
+		if field.value().name() == "dt" && text(field) == "Skill ID" {
+			let value = fields
+				.next()
+				.ok_or_else(|| Error::parse("Skill ID has no value!"))?;
+			let id = text(value)
+				.parse()
+				.map_err(|_| Error::parse(format!("Invalid skill ID {}", text(value))))?;
+			return Ok(Some(id));
+		}
+	}
+	Ok(None)
+}
+
+fn progression(content: ElementRef) -> Result<Vec<Progression>> {
+	let select_table = Selector::parse("table.skill-progression").unwrap();
+	let table = match content.select(&select_table).next() {
+		Some(table) => table,
+		// skills without an attribute don't have one
+		None => return Ok(vec![]),
+	};
+
+	let select_rows = Selector::parse("tr").unwrap();
+	let select_headers = Selector::parse("th").unwrap();
+	let select_cells = Selector::parse("td").unwrap();
+	let mut rows = table.select(&select_rows);
+	let ranks: Vec<String> = match rows.next() {
+		Some(header) => header.select(&select_headers).skip(1).map(text).collect(),
+		None => return Ok(vec![]),
+	};
+	if ranks.len() != MAX_RANK as usize + 1
+		|| ranks.iter().zip(0..).any(|(r, i)| *r != i.to_string())
+	{
+		return Err(Error::parse(format!(
+			"Progression should go from rank 0 to {}, but has {:?}",
+			MAX_RANK, ranks
+		)));
+	}
+
+	rows.map(|row| {
+		let label = row
+			.select(&select_headers)
+			.next()
+			.map(text)
+			.ok_or_else(|| Error::parse("Progression row has no label!"))?;
+		let values = row
+			.select(&select_cells)
+			.map(|cell| {
+				let value = text(cell);
+				value
+					.parse()
+					.map_err(|_| Error::parse(format!("Invalid {} value {}", label, value)))
+			})
+			.collect::<Result<Vec<f32>>>()?;
+		if values.len() != ranks.len() {
+			return Err(Error::parse(format!("{} doesn't cover every rank", label)));
+		}
+		Ok(Progression { label, values })
+	})
+	.collect()
+}
+
+/// The title of a section, without its edit link.
+fn headline(heading: ElementRef) -> String {
+	let select_headline = Selector::parse(".mw-headline").unwrap();
+	heading
+		.select(&select_headline)
+		.next()
+		.map(text)
+		.unwrap_or_else(|| text(heading))
+}
+
+/// The text of a list item, leaving out any list nested inside it.
+fn item_text(item: ElementRef) -> String {
+	fn collect(element: ElementRef, out: &mut String) {
+		for child in element.children() {
+			match child.value() {
+				Node::Text(text) => out.push_str(text),
+				Node::Element(el) if el.name() == "ul" || el.name() == "ol" => (),
+				Node::Element(_) => collect(ElementRef::wrap(child).unwrap(), out),
+				_ => (),
+			}
+		}
+	}
+	let mut out = String::new();
+	collect(item, &mut out);
+	collapse_whitespace(&out)
+}
+
+fn text(element: ElementRef) -> String {
+	collapse_whitespace(&element.text().collect::<String>())
+}
+
+fn collapse_whitespace(text: &str) -> String {
+	text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,185 @@
+use crate::error::Result;
+use crate::io;
+use crate::skill::{Attribute, GameMode, Resource, Skill};
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+/// What changed between two snapshots of our skill data.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct SkillDiff {
+	pub added: Vec<String>,
+	pub removed: Vec<String>,
+	pub changed: Vec<SkillChanges>,
+}
+
+/// Everything that changed about a single skill.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SkillChanges {
+	pub skill: String,
+	pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "field", rename_all = "snake_case")]
+pub enum Change {
+	Name {
+		old: String,
+		new: String,
+	},
+	Description {
+		old: String,
+		new: String,
+	},
+	Resources {
+		old: Vec<Resource>,
+		new: Vec<Resource>,
+	},
+	Attribute {
+		old: Option<Attribute>,
+		new: Option<Attribute>,
+	},
+	Elite {
+		old: bool,
+		new: bool,
+	},
+	GameModeSplit {
+		old: Option<GameMode>,
+		new: Option<GameMode>,
+	},
+}
+
+impl SkillDiff {
+	pub fn between(old: &[Skill], new: &[Skill]) -> Self {
+		let mut diff = Self::default();
+		for skill in new {
+			match old.iter().find(|other| skill.is_same_skill(other)) {
+				Some(old_skill) => {
+					let changes = changes(old_skill, skill);
+					if !changes.is_empty() {
+						diff.changed.push(SkillChanges {
+							skill: skill.variant_name(),
+							changes,
+						});
+					}
+				}
+				None => diff.added.push(skill.variant_name()),
+			}
+		}
+		diff.removed = old
+			.iter()
+			.filter(|skill| !new.iter().any(|other| skill.is_same_skill(other)))
+			.map(Skill::variant_name)
+			.collect();
+		diff
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+	}
+}
+
+/// Loads a snapshot of skill data, either a single file as written by `build_data_cache`,
+/// or a whole data directory.
+pub fn load_snapshot(path: &Path) -> Result<Vec<Skill>> {
+	if !path.is_dir() {
+		return io::load_skill_file(path);
+	}
+
+	let mut files: Vec<_> = path
+		.read_dir()?
+		.map(|entry| entry.map(|entry| entry.path()))
+		.collect::<std::io::Result<_>>()?;
+	files.retain(|file| {
+		let name = file.file_name().unwrap_or_default().to_string_lossy();
+		name.ends_with(".json") && !name.ends_with(".meta.json")
+	});
+	files.sort();
+
+	let mut skills = vec![];
+	for file in files {
+		skills.extend(io::load_skill_file(&file)?);
+	}
+	Ok(skills)
+}
+
+fn changes(old: &Skill, new: &Skill) -> Vec<Change> {
+	let mut changes = vec![];
+	if old.name != new.name {
+		changes.push(Change::Name {
+			old: old.name.clone(),
+			new: new.name.clone(),
+		});
+	}
+	if old.description != new.description {
+		changes.push(Change::Description {
+			old: old.description.clone(),
+			new: new.description.clone(),
+		});
+	}
+	if old.resources != new.resources {
+		changes.push(Change::Resources {
+			old: old.resources.clone(),
+			new: new.resources.clone(),
+		});
+	}
+	if old.attribute != new.attribute {
+		changes.push(Change::Attribute {
+			old: old.attribute,
+			new: new.attribute,
+		});
+	}
+	if old.is_elite != new.is_elite {
+		changes.push(Change::Elite {
+			old: old.is_elite,
+			new: new.is_elite,
+		});
+	}
+	if old.split_by_game_mode != new.split_by_game_mode {
+		changes.push(Change::GameModeSplit {
+			old: old.split_by_game_mode,
+			new: new.split_by_game_mode,
+		});
+	}
+	changes
+}
+
+impl fmt::Display for SkillDiff {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for skill in &self.added {
+			writeln!(f, "+ {}", skill)?;
+		}
+		for skill in &self.removed {
+			writeln!(f, "- {}", skill)?;
+		}
+		for skill in &self.changed {
+			writeln!(f, "~ {}", skill.skill)?;
+			for change in &skill.changes {
+				writeln!(f, "    {}", change)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl fmt::Display for Change {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Name { old, new } => write!(f, "name: {:?} -> {:?}", old, new),
+			Self::Description { old, new } => {
+				write!(f, "description: {:?} -> {:?}", old, new)
+			}
+			Self::Resources { old, new } => write!(f, "resources: {:?} -> {:?}", old, new),
+			Self::Attribute { old, new } => write!(
+				f,
+				"attribute: {} -> {}",
+				old.map_or("none", Attribute::name),
+				new.map_or("none", Attribute::name)
+			),
+			Self::Elite { old, new } => write!(f, "elite: {} -> {}", old, new),
+			Self::GameModeSplit { old, new } => {
+				write!(f, "game mode split: {:?} -> {:?}", old, new)
+			}
+		}
+	}
+}
@@ -0,0 +1,202 @@
+use crate::error::{Error, Result};
+use reqwest::blocking::Client;
+use reqwest::header::{
+	HeaderMap, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::StatusCode;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use std::{fs, io, thread};
+
+/// How hard we're allowed to hit the wiki.
+#[derive(Debug, Clone)]
+pub struct DownloadSettings {
+	/// How many downloads may run at the same time.
+	pub concurrency: usize,
+	/// The least time between the start of two requests, no matter how many run at the same time.
+	pub delay: Duration,
+	/// How often we try again after a server error or a timeout.
+	pub retries: u32,
+	/// How long we wait before the first retry. This doubles with each further retry.
+	pub backoff: Duration,
+	/// How long a single request may take.
+	pub timeout: Duration,
+}
+
+impl Default for DownloadSettings {
+	fn default() -> Self {
+		Self {
+			concurrency: 2,
+			delay: Duration::from_millis(500),
+			retries: 3,
+			backoff: Duration::from_secs(2),
+			timeout: Duration::from_secs(30),
+		}
+	}
+}
+
+/// A successful response.
+pub struct Download {
+	pub content_type: Option<String>,
+	/// Lets us ask the server whether the file changed since, see [`Downloader::get_if_modified`].
+	pub etag: Option<String>,
+	pub last_modified: Option<String>,
+	pub body: Vec<u8>,
+}
+
+impl Download {
+	/// Makes sure we didn't get an error page dressed up with a success status.
+	pub fn expect_image(self, url: &str) -> Result<Self> {
+		match &self.content_type {
+			Some(content_type) if content_type.starts_with("image/") => Ok(self),
+			other => Err(Error::Image(format!(
+				"{} is {}, not an image",
+				url,
+				other.as_deref().unwrap_or("of unknown type")
+			))),
+		}
+	}
+}
+
+/// A polite HTTP client that shares its request budget between all of its workers.
+pub struct Downloader {
+	client: Client,
+	settings: DownloadSettings,
+	pool: rayon::ThreadPool,
+	next_request: Mutex<Instant>,
+}
+
+impl Downloader {
+	pub fn new(settings: DownloadSettings) -> Result<Self> {
+		let client = Client::builder().timeout(settings.timeout).build()?;
+		let pool = rayon::ThreadPoolBuilder::new()
+			.num_threads(settings.concurrency.max(1))
+			.build()
+			.map_err(|err| Error::Io(io::Error::other(err)))?;
+
+		Ok(Self {
+			client,
+			settings,
+			pool,
+			next_request: Mutex::new(Instant::now()),
+		})
+	}
+
+	/// Gets `url`, trying again with backoff if the server has trouble or takes too long.
+	/// Fails right away on client errors like 404.
+	pub fn get(&self, url: &str) -> Result<Download> {
+		self.get_if_modified(url, None, None)?.ok_or_else(|| {
+			Error::parse(format!("{} claims to be unchanged, but we didn't ask", url))
+		})
+	}
+
+	/// Like [`get`](Self::get), but returns `None` if the server tells us `url` hasn't changed
+	/// since the response that came with `etag` and `last_modified`.
+	pub fn get_if_modified(
+		&self,
+		url: &str,
+		etag: Option<&str>,
+		last_modified: Option<&str>,
+	) -> Result<Option<Download>> {
+		let mut headers = HeaderMap::new();
+		if let Some(etag) = etag.and_then(|value| value.parse().ok()) {
+			headers.insert(IF_NONE_MATCH, etag);
+		}
+		if let Some(last_modified) = last_modified.and_then(|value| value.parse().ok()) {
+			headers.insert(IF_MODIFIED_SINCE, last_modified);
+		}
+
+		let mut attempt = 0;
+		loop {
+			match self.try_get(url, &headers) {
+				Err(err) if attempt < self.settings.retries && is_transient(&err) => {
+					thread::sleep(self.settings.backoff * 2_u32.pow(attempt));
+					attempt += 1;
+				}
+				result => return Ok(result?),
+			}
+		}
+	}
+
+	/// Runs `job` for every item, `concurrency` at a time, and returns the items that failed.
+	pub fn run<'a, T, F>(&self, items: &'a [T], job: F) -> Vec<(&'a T, Error)>
+	where
+		T: Sync,
+		F: Fn(&T) -> Result<()> + Sync,
+	{
+		use rayon::prelude::*;
+
+		self.pool.install(|| {
+			items
+				.par_iter()
+				.filter_map(|item| job(item).err().map(|err| (item, err)))
+				.collect()
+		})
+	}
+
+	fn try_get(&self, url: &str, headers: &HeaderMap) -> reqwest::Result<Option<Download>> {
+		self.wait_for_turn();
+		let response = self
+			.client
+			.get(url)
+			.headers(headers.clone())
+			.send()?
+			.error_for_status()?;
+		if response.status() == StatusCode::NOT_MODIFIED {
+			return Ok(None);
+		}
+
+		let header = |name| {
+			response
+				.headers()
+				.get(name)
+				.and_then(|value| value.to_str().ok())
+				.map(str::to_owned)
+		};
+		let content_type = header(CONTENT_TYPE);
+		let etag = header(ETAG);
+		let last_modified = header(LAST_MODIFIED);
+		Ok(Some(Download {
+			content_type,
+			etag,
+			last_modified,
+			body: response.bytes()?.to_vec(),
+		}))
+	}
+
+	/// Blocks until we may send our next request.
+	fn wait_for_turn(&self) {
+		let mut next_request = self.next_request.lock().unwrap();
+		let now = Instant::now();
+		if *next_request > now {
+			thread::sleep(*next_request - now);
+		}
+		*next_request = Instant::now() + self.settings.delay;
+	}
+}
+
+fn is_transient(err: &reqwest::Error) -> bool {
+	match err.status() {
+		Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+		None => err.is_timeout() || err.is_connect(),
+	}
+}
+
+/// Writes to a temporary file next to `path` first, so an interrupted run never leaves
+/// a half-written file behind that we'd mistake for a finished one.
+pub fn write_atomically<F>(path: &Path, write: F) -> Result<()>
+where
+	F: FnOnce(&Path) -> Result<()>,
+{
+	let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+	temp_name.push(".part");
+	let temp_path: PathBuf = path.with_file_name(temp_name);
+
+	if let Err(err) = write(&temp_path) {
+		let _ = fs::remove_file(&temp_path);
+		return Err(err);
+	}
+	fs::rename(&temp_path, path)?;
+	Ok(())
+}
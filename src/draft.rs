@@ -0,0 +1,151 @@
+use crate::error::{Error, Result};
+use crate::packs::PackRules;
+use crate::skill::{Profession, Skill};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::Serialize;
+
+/// How a draft is set up.
+#[derive(Debug, Clone)]
+pub struct DraftSettings {
+	/// Players who pick their own cards. They sit first at the table.
+	pub real_players: usize,
+	/// Players who discard a random card from each pack instead of picking one.
+	pub fake_players: usize,
+	/// Number of cards in each booster pack.
+	pub pack_size: usize,
+	/// Number of packs each player opens.
+	pub rounds: usize,
+	/// Seed for shuffling the pool and for the fake players' discards.
+	pub seed: u64,
+	/// What has to go into every pack.
+	pub rules: PackRules,
+	/// The professions of the real players, in seating order, for rules that depend on them.
+	/// Players past the end of the list don't have one.
+	pub professions: Vec<Profession>,
+}
+
+impl DraftSettings {
+	pub fn players(&self) -> usize {
+		self.real_players + self.fake_players
+	}
+
+	pub fn profession(&self, player: usize) -> Option<Profession> {
+		self.professions
+			.get(player)
+			.copied()
+			.filter(|_| player < self.real_players)
+	}
+}
+
+/// What a real player sees when it's their turn to pick.
+pub struct Turn<'a> {
+	/// The player's seat at the table, counting from 0.
+	pub player: usize,
+	/// The round we're in, counting from 1.
+	pub round: usize,
+	/// The cards left in the pack in front of the player.
+	pub pack: &'a [Skill],
+	/// The cards the player picked so far.
+	pub pool: &'a [Skill],
+}
+
+/// Everything that happened to the cards of a draft.
+#[derive(Debug, Serialize)]
+pub struct DraftResult {
+	pub seed: u64,
+	/// One pool per seat, in seating order.
+	pub pools: Vec<PlayerPool>,
+	/// The cards the fake players threw away.
+	pub discarded: Vec<Skill>,
+}
+
+/// The cards a single player ended up with.
+#[derive(Debug, Serialize)]
+pub struct PlayerPool {
+	pub player: usize,
+	pub is_fake: bool,
+	pub picks: Vec<Skill>,
+}
+
+/// Runs a booster draft over `pool`, just like the one described in the Readme,
+/// with packs that follow the settings' [`PackRules`].
+///
+/// Every player opens a pack, and everyone takes a card from the pack in front of them,
+/// then passes the rest to their left, until the packs are empty. Then the next round starts.
+/// Real players pick through `pick`, which returns the index of the chosen card in the pack.
+/// If it fails, so does the draft.
+/// Fake players shuffle their pack and discard a card instead.
+pub fn run_draft<F>(
+	mut pool: Vec<Skill>,
+	settings: &DraftSettings,
+	mut pick: F,
+) -> Result<DraftResult>
+where
+	F: FnMut(&Turn) -> Result<usize>,
+{
+	let players = settings.players();
+	if players == 0 || settings.pack_size == 0 {
+		return Err(Error::draft(
+			"A draft needs players and packs with cards in them",
+		));
+	}
+	let mut rng = StdRng::seed_from_u64(settings.seed);
+	pool.shuffle(&mut rng);
+
+	// Deal the packs of every round before the first pick, so we can't run out of cards
+	// that follow the rules halfway through the draft.
+	let professions: Vec<Option<Profession>> = (0..settings.rounds)
+		.flat_map(|_| (0..players).map(|player| settings.profession(player)))
+		.collect();
+	let mut all_packs = settings
+		.rules
+		.deal_packs(&mut pool, settings.pack_size, &professions)?
+		.into_iter();
+
+	let mut pools: Vec<PlayerPool> = (0..players)
+		.map(|player| PlayerPool {
+			player,
+			is_fake: player >= settings.real_players,
+			picks: vec![],
+		})
+		.collect();
+	let mut discarded = vec![];
+
+	for round in 1..=settings.rounds {
+		let mut packs: Vec<Vec<Skill>> = all_packs.by_ref().take(players).collect();
+
+		for _ in 0..settings.pack_size {
+			for (player, pack) in packs.iter_mut().enumerate() {
+				let seat = &mut pools[player];
+				if seat.is_fake {
+					pack.shuffle(&mut rng);
+					discarded.push(pack.remove(0));
+				} else {
+					let idx = pick(&Turn {
+						player,
+						round,
+						pack,
+						pool: &seat.picks,
+					})?;
+					if idx >= pack.len() {
+						return Err(Error::draft(format!(
+							"Player {} picked card {} out of a pack of {}",
+							player + 1,
+							idx + 1,
+							pack.len()
+						)));
+					}
+					seat.picks.push(pack.remove(idx));
+				}
+			}
+			// pass to the left, so everyone gets the pack of the player on their right
+			packs.rotate_right(1);
+		}
+	}
+
+	Ok(DraftResult {
+		seed: settings.seed,
+		pools,
+		discarded,
+	})
+}
@@ -0,0 +1,165 @@
+use std::{error, fmt, io, result};
+
+/// Everything that can go wrong while scraping skills and turning them into cards.
+#[derive(Debug)]
+pub enum Error {
+	/// We couldn't talk to the wiki.
+	Network(reqwest::Error),
+	/// The wiki (or a cache file) didn't look the way we expected.
+	Parse(String),
+	/// We couldn't read, edit, or write an image.
+	Image(String),
+	/// We couldn't read or write a file.
+	Io(io::Error),
+	/// We couldn't load the font we draw card text with.
+	Font(String),
+	/// A build template code we can't read, or skills that don't make a valid build.
+	Template(String),
+	/// A draft we can't run with the cards and players we have.
+	Draft(String),
+	/// We couldn't write an exported SQLite database.
+	Database(rusqlite::Error),
+	/// We couldn't start the threads that render cards.
+	Threads(rayon::ThreadPoolBuildError),
+	/// Print settings that don't fit a page, or a PDF we couldn't write.
+	Print(String),
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+impl Error {
+	pub fn parse(message: impl Into<String>) -> Self {
+		Self::Parse(message.into())
+	}
+
+	pub fn draft(message: impl Into<String>) -> Self {
+		Self::Draft(message.into())
+	}
+
+	pub fn template(message: impl Into<String>) -> Self {
+		Self::Template(message.into())
+	}
+
+	pub fn print(message: impl Into<String>) -> Self {
+		Self::Print(message.into())
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Network(err) => write!(f, "Network error: {}", err),
+			Self::Parse(msg) => write!(f, "Parse error: {}", msg),
+			Self::Image(msg) => write!(f, "Image error: {}", msg),
+			Self::Io(err) => write!(f, "IO error: {}", err),
+			Self::Font(msg) => write!(f, "Font error: {}", msg),
+			Self::Template(msg) => write!(f, "Template error: {}", msg),
+			Self::Draft(msg) => write!(f, "Draft error: {}", msg),
+			Self::Database(err) => write!(f, "Database error: {}", err),
+			Self::Threads(err) => write!(f, "Thread pool error: {}", err),
+			Self::Print(msg) => write!(f, "Print error: {}", msg),
+		}
+	}
+}
+
+impl error::Error for Error {
+	fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+		match self {
+			Self::Network(err) => Some(err),
+			Self::Io(err) => Some(err),
+			Self::Database(err) => Some(err),
+			Self::Threads(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<reqwest::Error> for Error {
+	fn from(err: reqwest::Error) -> Self {
+		Self::Network(err)
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(err: io::Error) -> Self {
+		Self::Io(err)
+	}
+}
+
+impl From<serde_json::Error> for Error {
+	fn from(err: serde_json::Error) -> Self {
+		Self::Parse(err.to_string())
+	}
+}
+
+impl From<toml::de::Error> for Error {
+	fn from(err: toml::de::Error) -> Self {
+		Self::Parse(err.to_string())
+	}
+}
+
+impl From<csv::Error> for Error {
+	fn from(err: csv::Error) -> Self {
+		if err.is_io_error() {
+			Self::Io(err.into())
+		} else {
+			Self::Parse(err.to_string())
+		}
+	}
+}
+
+impl From<rusqlite::Error> for Error {
+	fn from(err: rusqlite::Error) -> Self {
+		Self::Database(err)
+	}
+}
+
+impl From<rayon::ThreadPoolBuildError> for Error {
+	fn from(err: rayon::ThreadPoolBuildError) -> Self {
+		Self::Threads(err)
+	}
+}
+
+impl From<image::ImageError> for Error {
+	fn from(err: image::ImageError) -> Self {
+		match err {
+			image::ImageError::IoError(err) => Self::Io(err),
+			err => Self::Image(err.to_string()),
+		}
+	}
+}
+
+impl From<raster::error::RasterError> for Error {
+	fn from(err: raster::error::RasterError) -> Self {
+		match err {
+			raster::error::RasterError::Io(err) => Self::Io(err),
+			err => Self::Image(format!("{:?}", err)),
+		}
+	}
+}
+
+/// Collects the skills that failed somewhere along the pipeline, so we can carry on with the rest.
+#[derive(Debug, Default)]
+pub struct Report {
+	pub failures: Vec<(String, Error)>,
+}
+
+impl Report {
+	pub fn record(&mut self, skill: impl Into<String>, error: Error) {
+		self.failures.push((skill.into(), error));
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.failures.is_empty()
+	}
+}
+
+impl fmt::Display for Report {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "{} skill(s) failed:", self.failures.len())?;
+		for (skill, error) in &self.failures {
+			writeln!(f, "  {}: {}", skill, error)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,142 @@
+use crate::download::write_atomically;
+use crate::error::{Error, Result};
+use crate::skill::{Skill, SkillRecord};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// The file formats we can export skills to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ExportFormat {
+	/// A header and one [`SkillRecord`] per row, the same as [`DataFile`](crate::source::DataFile) reads.
+	Csv,
+	/// One [`SkillRecord`] as a JSON object per line.
+	JsonLines,
+	/// A database with a single `skills` table, with one [`SkillRecord`] per row.
+	Sqlite,
+}
+
+impl ExportFormat {
+	/// Picks the format by the file extension: `.csv`, `.jsonl`, or `.sqlite`/`.db`.
+	pub fn from_path(path: &Path) -> Result<Self> {
+		match path.extension().and_then(|ext| ext.to_str()) {
+			Some("csv") => Ok(Self::Csv),
+			Some("jsonl") => Ok(Self::JsonLines),
+			Some("sqlite") | Some("db") => Ok(Self::Sqlite),
+			_ => Err(Error::parse(format!(
+				"Don't know how to export to {}, it should be .csv, .jsonl, .sqlite or .db",
+				path.display()
+			))),
+		}
+	}
+}
+
+/// Writes `skills` to `path`, in the format its extension asks for.
+/// Replaces whatever was there before.
+pub fn export_skills(skills: &[Skill], path: &Path) -> Result<()> {
+	let format = ExportFormat::from_path(path)?;
+	let records: Vec<SkillRecord> = skills.iter().map(SkillRecord::from).collect();
+	write_atomically(path, |temp| match format {
+		ExportFormat::Csv => write_csv(&records, temp),
+		ExportFormat::JsonLines => write_json_lines(&records, temp),
+		ExportFormat::Sqlite => write_sqlite(&records, temp),
+	})
+}
+
+fn write_csv(records: &[SkillRecord], path: &Path) -> Result<()> {
+	let mut writer = csv::Writer::from_path(path)?;
+	for record in records {
+		writer.serialize(record)?;
+	}
+	writer.flush()?;
+	Ok(())
+}
+
+fn write_json_lines(records: &[SkillRecord], path: &Path) -> Result<()> {
+	let mut writer = BufWriter::new(File::create(path)?);
+	for record in records {
+		serde_json::to_writer(&mut writer, record)?;
+		writer.write_all(b"\n")?;
+	}
+	writer.flush()?;
+	Ok(())
+}
+
+fn write_sqlite(records: &[SkillRecord], path: &Path) -> Result<()> {
+	// a leftover from an export that failed halfway would already have the table
+	if path.exists() {
+		std::fs::remove_file(path)?;
+	}
+	let mut connection = Connection::open(path)?;
+	let transaction = connection.transaction()?;
+	transaction.execute_batch(
+		"CREATE TABLE skills (
+			id INTEGER,
+			name TEXT NOT NULL,
+			profession TEXT NOT NULL,
+			skill_type TEXT NOT NULL,
+			attribute TEXT,
+			campaign TEXT NOT NULL,
+			description TEXT NOT NULL,
+			is_elite INTEGER NOT NULL,
+			is_quest_reward INTEGER NOT NULL,
+			is_pve_only INTEGER NOT NULL,
+			split_by_game_mode TEXT,
+			icon_url TEXT NOT NULL,
+			adrenaline INTEGER,
+			overcast INTEGER,
+			upkeep INTEGER,
+			sacrifice INTEGER,
+			energy INTEGER,
+			cast REAL,
+			recharge INTEGER,
+			progression TEXT,
+			related_skills TEXT,
+			notes TEXT,
+			acquisition TEXT
+		)",
+	)?;
+	{
+		let mut insert = transaction.prepare(
+			"INSERT INTO skills VALUES
+			(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19,
+			?20, ?21, ?22, ?23)",
+		)?;
+		for record in records {
+			insert.execute(params![
+				record.id,
+				record.name,
+				record.profession.to_string(),
+				record.skill_type.name(),
+				record.attribute.map(|attribute| attribute.name()),
+				record.campaign.to_string(),
+				record.description,
+				record.is_elite,
+				record.is_quest_reward,
+				record.is_pve_only,
+				record.split_by_game_mode.map(|mode| mode.to_string()),
+				record.icon_url,
+				record.adrenaline,
+				record.overcast,
+				record.upkeep,
+				record.sacrifice,
+				record.energy,
+				record.cast,
+				record.recharge,
+				json(&record.progression)?,
+				json(&record.related_skills)?,
+				json(&record.notes)?,
+				json(&record.acquisition)?,
+			])?;
+		}
+	}
+	transaction.commit()?;
+	Ok(())
+}
+
+/// The same JSON text the other formats put in a details column.
+fn json<T: Serialize>(value: &Option<T>) -> Result<Option<String>> {
+	Ok(value.as_ref().map(serde_json::to_string).transpose()?)
+}
@@ -0,0 +1,124 @@
+use crate::error::Result;
+use crate::skill::{Attribute, Campaign, GameMode, Profession, Skill, SkillType};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Which skills make it into the card pool.
+///
+/// Read from a TOML file like this one, for a pool of Prophecies skills without PvE-only skills:
+///
+/// ```toml
+/// [include]
+/// campaigns = ["Core", "Prophecies"]
+///
+/// [exclude]
+/// names = ["Resurrection Signet"]
+/// pve_only = true
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PoolFilter {
+	/// Which variants of skills split by game mode to keep. Skills that aren't split are always kept.
+	#[serde(default = "default_game_modes")]
+	pub game_modes: Vec<GameMode>,
+	/// A skill has to match all of these.
+	#[serde(default)]
+	pub include: Criteria,
+	/// A skill mustn't match any of these.
+	#[serde(default)]
+	pub exclude: Criteria,
+}
+
+/// Things to match skills by. Criteria that are left out match every skill when including,
+/// and none when excluding.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Criteria {
+	/// Skill names, without a (PvP) or (Codex) suffix.
+	#[serde(default)]
+	pub names: Vec<String>,
+	#[serde(default)]
+	pub professions: Vec<Profession>,
+	#[serde(default)]
+	pub campaigns: Vec<Campaign>,
+	/// Attribute names, like `Swordsmanship` or `Kurzick rank`.
+	#[serde(default)]
+	pub attributes: Vec<Attribute>,
+	/// Skill types without "Elite", like `Hex Spell` or `Stance`.
+	#[serde(default)]
+	pub skill_types: Vec<SkillType>,
+	pub elite: Option<bool>,
+	pub pve_only: Option<bool>,
+	pub quest_reward: Option<bool>,
+}
+
+fn default_game_modes() -> Vec<GameMode> {
+	vec![GameMode::PvE]
+}
+
+impl Default for PoolFilter {
+	/// The PvE versions of all skills.
+	fn default() -> Self {
+		Self {
+			game_modes: default_game_modes(),
+			include: Criteria::default(),
+			exclude: Criteria::default(),
+		}
+	}
+}
+
+impl PoolFilter {
+	pub fn load(path: &Path) -> Result<Self> {
+		Ok(toml::from_str(&fs::read_to_string(path)?)?)
+	}
+
+	pub fn keeps(&self, skill: &Skill) -> bool {
+		let game_mode = match skill.split_by_game_mode {
+			Some(mode) => self.game_modes.contains(&mode),
+			None => true,
+		};
+		game_mode
+			&& self.include.matches(skill).iter().all(|&matched| matched)
+			&& !self.exclude.matches(skill).iter().any(|&matched| matched)
+	}
+
+	pub fn apply(&self, skills: Vec<Skill>) -> Vec<Skill> {
+		skills
+			.into_iter()
+			.filter(|skill| self.keeps(skill))
+			.collect()
+	}
+}
+
+impl Criteria {
+	/// Whether `skill` matches each criterion that is given.
+	fn matches(&self, skill: &Skill) -> Vec<bool> {
+		let mut matches = vec![];
+		if !self.names.is_empty() {
+			matches.push(self.names.contains(&skill.name));
+		}
+		if !self.professions.is_empty() {
+			matches.push(self.professions.contains(&skill.profession));
+		}
+		if !self.campaigns.is_empty() {
+			matches.push(self.campaigns.contains(&skill.campaign()));
+		}
+		if !self.attributes.is_empty() {
+			matches.push(matches!(skill.attribute, Some(a) if self.attributes.contains(&a)));
+		}
+		if !self.skill_types.is_empty() {
+			matches.push(self.skill_types.contains(&skill.skill_type()));
+		}
+		if let Some(elite) = self.elite {
+			matches.push(skill.is_elite == elite);
+		}
+		if let Some(pve_only) = self.pve_only {
+			matches.push(skill.is_pve_only() == pve_only);
+		}
+		if let Some(quest_reward) = self.quest_reward {
+			matches.push(skill.is_quest_reward() == quest_reward);
+		}
+		matches
+	}
+}
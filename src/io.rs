@@ -1,19 +1,73 @@
-use crate::skill::{GameMode, Profession, Skill};
-use image;
-use reqwest;
+use crate::diff::SkillDiff;
+use crate::download::write_atomically;
+use crate::error::{Error, Report, Result};
+use crate::filter::PoolFilter;
+use crate::skill::{Attribute, GameMode, Profession, Skill};
+use crate::source::{skill_page, SkillSource, Wiki};
+use image::ImageFormat;
 use scraper::{Html, Selector};
-use serde_json;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
-use std::{fs, thread, time};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-fn is_allegiance_rank(skill: &Skill) -> bool {
-	match skill.attribute {
-		Some(ref attr) if attr.starts_with("Allegiance") => true,
-		_ => false,
+/// Where we keep our downloads and where we put the cards we generate.
+#[derive(Debug, Clone)]
+pub struct Directories {
+	pub cache: PathBuf,
+	pub cards: PathBuf,
+}
+
+impl Default for Directories {
+	fn default() -> Self {
+		Self {
+			cache: PathBuf::from("cache"),
+			cards: PathBuf::from("cards"),
+		}
+	}
+}
+
+impl Directories {
+	pub fn data(&self) -> PathBuf {
+		self.cache.join("data")
+	}
+
+	pub fn images(&self) -> PathBuf {
+		self.cache.join("images")
+	}
+
+	pub fn decks(&self) -> PathBuf {
+		self.cards.join("decks")
+	}
+
+	pub fn data_path(&self, profession: Profession) -> PathBuf {
+		self.data().join(format!("{}.json", profession))
+	}
+
+	pub fn meta_path(&self, profession: Profession) -> PathBuf {
+		self.data().join(format!("{}.meta.json", profession))
+	}
+
+	pub fn icon_path(&self, skill: &Skill) -> PathBuf {
+		self.images().join(format!("{}.jpg", skill.file_name()))
 	}
+
+	pub fn card_path(&self, skill: &Skill) -> PathBuf {
+		self.cards.join(format!("{}.png", skill.file_name()))
+	}
+}
+
+fn is_allegiance_rank(skill: &Skill) -> bool {
+	skill.attribute == Some(Attribute::AllegianceRank)
 }
 
-fn touch_up_skills(mut skills: Vec<Skill>) -> Vec<Skill> {
+/// Marks the PvE variants of skills that were split by game mode,
+/// and turns each allegiance skill into a Kurzick and a Luxon variant.
+pub fn touch_up_skills(mut skills: Vec<Skill>) -> Vec<Skill> {
 	let pvp_skill_names: Vec<String> = skills
 		.iter()
 		.filter_map(|s| {
@@ -33,15 +87,15 @@ fn touch_up_skills(mut skills: Vec<Skill>) -> Vec<Skill> {
 
 	let allegiance: Vec<&mut Skill> = skills
 		.iter_mut()
-		.filter(|s| is_allegiance_rank(&s))
+		.filter(|s| is_allegiance_rank(s))
 		.collect();
 
 	let mut luxon_variants = vec![];
 
 	for skill in allegiance {
 		let mut luxon_variant = skill.clone();
-		luxon_variant.attribute = Some("Luxon rank".to_owned());
-		skill.attribute = Some("Kurzick rank".to_owned());
+		luxon_variant.attribute = Some(Attribute::LuxonRank);
+		skill.attribute = Some(Attribute::KurzickRank);
 		luxon_variants.push(luxon_variant);
 	}
 
@@ -49,90 +103,440 @@ fn touch_up_skills(mut skills: Vec<Skill>) -> Vec<Skill> {
 	skills
 }
 
-pub fn build_data_cache(profession: Profession) {
-	let path = &format!("cache/data/{}.json", profession);
-	if fs::metadata(path).is_ok() {
+/// Gets the skills of `profession` from `source` into our data cache.
+/// Rows we can't make sense of are recorded in `report` and left out.
+pub fn build_data_cache(
+	dirs: &Directories,
+	source: &dyn SkillSource,
+	profession: Profession,
+	force: bool,
+	report: &mut Report,
+) -> Result<()> {
+	let path = dirs.data_path(profession);
+	if !force && path.exists() {
 		// already exists
-		return;
+		return Ok(());
+	}
+	match source.fetch(profession, None, report)? {
+		Some(fetched) => store_skill_cache(dirs, profession, &fetched.skills, &fetched.meta),
+		None => Ok(()),
+	}
+}
+
+/// Gets the skills of `profession` from `source` again if they changed since we cached them,
+/// and tells us which skills changed.
+pub fn refresh_data_cache(
+	dirs: &Directories,
+	source: &dyn SkillSource,
+	profession: Profession,
+	report: &mut Report,
+) -> Result<CacheUpdate> {
+	let (old_skills, old_meta) = if dirs.data_path(profession).exists() {
+		(
+			load_skill_cache(dirs, profession)?,
+			load_cache_meta(dirs, profession)?,
+		)
+	} else {
+		(vec![], None)
+	};
+
+	let mut fetched = match source.fetch(profession, old_meta.as_ref(), report)? {
+		Some(fetched) => fetched,
+		None => {
+			// The source says nothing changed, we just remember that we asked.
+			if let Some(meta) = old_meta {
+				let meta = CacheMeta {
+					fetched_at: now(),
+					..meta
+				};
+				store_cache_meta(dirs, profession, &meta)?;
+			}
+			return Ok(CacheUpdate::default());
+		}
+	};
+
+	if old_meta.map(|old| old.content_hash).as_ref() == Some(&fetched.meta.content_hash) {
+		store_cache_meta(dirs, profession, &fetched.meta)?;
+		return Ok(CacheUpdate::default());
+	}
+
+	keep_details(&old_skills, &mut fetched.skills);
+	let update = CacheUpdate::between(&old_skills, &fetched.skills);
+	store_skill_cache(dirs, profession, &fetched.skills, &fetched.meta)?;
+	Ok(update)
+}
+
+/// Details only come from the skills' own pages, so we hold on to them for as long as
+/// the skill itself doesn't change. The same goes for IDs we took from those pages.
+fn keep_details(old_skills: &[Skill], skills: &mut [Skill]) {
+	for skill in skills.iter_mut() {
+		let old = old_skills
+			.iter()
+			.filter(|old| old.is_same_skill(skill) && old.details.is_some())
+			.find(|old| {
+				let mut with_details = skill.clone();
+				with_details.id = skill.id.or(old.id);
+				with_details.details = old.details.clone();
+				with_details == **old
+			});
+		if let Some(old) = old {
+			skill.id = skill.id.or(old.id);
+			skill.details = old.details.clone();
+		}
+	}
+}
+
+/// Scrapes the own wiki page of each cached skill of `profession` we don't have details for yet,
+/// and adds what we find to the cache. Skills whose page we can't get are recorded in `report`.
+///
+/// Skills the skill lists had no ID for take the one from their page. Skills whose page
+/// has a different ID than the lists are recorded in `report` too, and keep what they had.
+pub fn build_details_cache(
+	dirs: &Directories,
+	wiki: &Wiki,
+	profession: Profession,
+	force: bool,
+	report: &mut Report,
+) -> Result<()> {
+	let mut skills = load_skill_cache(dirs, profession)?;
+
+	// Allegiance skills share a page between their Kurzick and Luxon variants.
+	let mut seen = HashSet::new();
+	let missing: Vec<&Skill> = skills
+		.iter()
+		.filter(|skill| force || skill.details.is_none())
+		.filter(|skill| seen.insert(skill_page(skill)))
+		.collect();
+	if missing.is_empty() {
+		return Ok(());
+	}
+
+	let found = Mutex::new(HashMap::new());
+	let failures = wiki.downloader().run(&missing, |skill| {
+		let details = wiki.skill_details(skill)?;
+		found.lock().unwrap().insert(skill_page(skill), details);
+		Ok(())
+	});
+	for (skill, err) in failures {
+		report.record(&skill.name, err);
+	}
+
+	let found = found.into_inner().unwrap();
+	let mut skills_per_page = HashMap::new();
+	for skill in &skills {
+		*skills_per_page.entry(skill_page(skill)).or_insert(0) += 1;
+	}
+	for skill in &mut skills {
+		let page = skill_page(skill);
+		let details = match found.get(&page) {
+			Some(details) => details,
+			None => continue,
+		};
+		// A page shared by several skills only has the ID of one of them.
+		if skills_per_page[&page] == 1 {
+			match (skill.id, details.id) {
+				(Some(id), Some(page_id)) if id != page_id => {
+					report.record(
+						&skill.name,
+						Error::parse(format!(
+							"The skill list says its ID is {}, but its page says {}",
+							id, page_id
+						)),
+					);
+					continue;
+				}
+				(None, page_id) => skill.id = page_id,
+				_ => {}
+			}
+		}
+		skill.details = Some(details.clone());
+	}
+	store_skills(dirs, profession, &skills)
+}
+
+fn store_skill_cache(
+	dirs: &Directories,
+	profession: Profession,
+	skills: &[Skill],
+	meta: &CacheMeta,
+) -> Result<()> {
+	store_skills(dirs, profession, skills)?;
+	store_cache_meta(dirs, profession, meta)
+}
+
+fn store_skills(dirs: &Directories, profession: Profession, skills: &[Skill]) -> Result<()> {
+	let json = serde_json::to_string(skills)?;
+	write_atomically(&dirs.data_path(profession), |temp| {
+		Ok(fs::write(temp, json)?)
+	})
+}
+
+fn store_cache_meta(dirs: &Directories, profession: Profession, meta: &CacheMeta) -> Result<()> {
+	let json = serde_json::to_string_pretty(meta)?;
+	write_atomically(&dirs.meta_path(profession), |temp| {
+		Ok(fs::write(temp, json)?)
+	})
+}
+
+/// Where and when we got the cached skills of a profession, so we can tell whether they're stale.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheMeta {
+	/// When we last checked the wiki, in seconds since the Unix epoch.
+	pub fetched_at: u64,
+	pub url: String,
+	pub etag: Option<String>,
+	pub last_modified: Option<String>,
+	/// SHA-256 of the page we parsed the skills from.
+	pub content_hash: String,
+}
+
+impl CacheMeta {
+	/// Describes skills we just got from `url`, out of `content`.
+	pub fn new(url: impl Into<String>, content: &[u8]) -> Self {
+		Self {
+			fetched_at: now(),
+			url: url.into(),
+			etag: None,
+			last_modified: None,
+			content_hash: format!("{:x}", Sha256::digest(content)),
+		}
+	}
+}
+
+fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|since| since.as_secs())
+		.unwrap_or_default()
+}
+
+/// Returns `None` for caches written before we kept metadata.
+pub fn load_cache_meta(dirs: &Directories, profession: Profession) -> Result<Option<CacheMeta>> {
+	let path = dirs.meta_path(profession);
+	if !path.exists() {
+		return Ok(None);
+	}
+	Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+}
+
+/// The skills that differ between two versions of a cached skill list.
+#[derive(Debug, Default)]
+pub struct CacheUpdate {
+	/// Skills that are new or different, in their new version.
+	pub changed: Vec<Skill>,
+	/// Skills that aren't on the list anymore.
+	pub removed: Vec<Skill>,
+	/// Skills whose icon moved to a different URL, in their new version.
+	pub moved_icons: Vec<Skill>,
+	/// What exactly changed, for telling the user.
+	pub diff: SkillDiff,
+}
+
+impl CacheUpdate {
+	pub fn between(old: &[Skill], new: &[Skill]) -> Self {
+		let changed = new
+			.iter()
+			.filter(|skill| !old.contains(skill))
+			.cloned()
+			.collect();
+		let removed = old
+			.iter()
+			.filter(|skill| !new.iter().any(|other| skill.is_same_skill(other)))
+			.cloned()
+			.collect();
+		let moved_icons = new
+			.iter()
+			.filter(|skill| {
+				old.iter()
+					.any(|other| skill.is_same_skill(other) && skill.icon_url != other.icon_url)
+			})
+			.cloned()
+			.collect();
+		Self {
+			changed,
+			removed,
+			moved_icons,
+			diff: SkillDiff::between(old, new),
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.changed.is_empty() && self.removed.is_empty()
 	}
-	let url = format!(
-		"https://wiki.guildwars.com/wiki/List_of_{}_skills",
-		profession.to_string().to_lowercase()
-	);
-	let raw_html = reqwest::blocking::get(&url)
-		.expect(&format!("Unable to get response from {}", url))
-		.text()
-		.expect(&format!("Failed to get response text from {}", url));
-	let skills = touch_up_skills(parse_skills(&raw_html));
+}
 
-	fs::write(path, serde_json::to_string(&skills).unwrap())
-		.expect(&format!("Couldn't write to file {}", path));
+pub fn load_skill_cache(dirs: &Directories, profession: Profession) -> Result<Vec<Skill>> {
+	load_skill_file(&dirs.data_path(profession))
 }
 
-pub fn load_skill_cache(profession: Profession) -> Vec<Skill> {
-	let path = format!("cache/data/{}.json", profession);
-	let raw_skills = fs::read_to_string(path).expect("Couldn't read from file!");
-	let skills: Vec<Skill> = serde_json::from_str(&raw_skills).unwrap();
-	return skills;
+/// Loads a list of skills as written by `build_data_cache`.
+pub fn load_skill_file(path: &Path) -> Result<Vec<Skill>> {
+	let raw_skills = fs::read_to_string(path)?;
+	Ok(serde_json::from_str(&raw_skills)?)
+}
+
+/// Loads the cached skills of all `professions` that make it through `filter`.
+pub fn load_skills(
+	dirs: &Directories,
+	professions: &[Profession],
+	filter: &PoolFilter,
+) -> Result<Vec<Skill>> {
+	let mut skills = vec![];
+	for &profession in professions {
+		skills.extend(filter.apply(load_skill_cache(dirs, profession)?));
+	}
+	Ok(skills)
 }
 
-fn parse_skills(raw_html: &str) -> Vec<Skill> {
-	let page = Html::parse_fragment(&raw_html);
+/// Parses the skill table of a `List_of_<profession>_skills` wiki page.
+/// Rows we can't make sense of are recorded in `report` and left out.
+pub fn parse_skills(raw_html: &str, report: &mut Report) -> Vec<Skill> {
+	let page = Html::parse_fragment(raw_html);
 	let select_table_rows = Selector::parse("table.sortable tbody tr[data-name]").unwrap();
 	let rows = page.select(&select_table_rows);
-	let skills: Vec<Skill> = rows.map(|row| Skill::try_from(row).unwrap()).collect();
+	let mut skills = vec![];
+	for row in rows {
+		match Skill::try_from(row) {
+			Ok(skill) => skills.push(skill),
+			Err(err) => report.record(row.value().attr("data-name").unwrap_or_default(), err),
+		}
+	}
 	skills
 }
 
-pub fn create_directories() {
+/// Parses the wiki's list of skill IDs into the ID of each skill, by its variant name.
+pub fn parse_skill_ids(raw_html: &str) -> HashMap<String, u16> {
+	let page = Html::parse_fragment(raw_html);
+	let select_table_rows = Selector::parse("table tr").unwrap();
+	let select_cols = Selector::parse("td").unwrap();
+	page.select(&select_table_rows)
+		.filter_map(|row| {
+			let mut cols = row
+				.select(&select_cols)
+				.map(|col| col.text().collect::<String>());
+			let id = cols.next()?.trim().parse().ok()?;
+			let name = cols.next()?.trim().to_owned();
+			Some((name, id))
+		})
+		.collect()
+}
+
+/// Gives each of `skills` its ID, as parsed by [`parse_skill_ids`].
+/// Skills that aren't on the list keep going by their name.
+pub fn assign_skill_ids(skills: &mut [Skill], ids: &HashMap<String, u16>) {
+	for skill in skills {
+		skill.id = ids.get(&skill.variant_name()).copied();
+	}
+}
+
+pub fn create_directories(dirs: &Directories) -> Result<()> {
 	let mut dir_builder = fs::DirBuilder::new();
 	dir_builder.recursive(true);
-	dir_builder
-		.create("cache/data")
-		.expect("Couldn't create image directory!");
-	dir_builder
-		.create("cache/images")
-		.expect("Couldn't create data directory!");
-	dir_builder
-		.create("cards")
-		.expect("Couldn't create cards directory!");
-	dir_builder
-		.create("cards/decks")
-		.expect("Couldn't create decks directory!");
-}
-
-pub fn build_image_cache(skills: &[Skill]) {
-	let client = reqwest::blocking::Client::new();
-	let delay = time::Duration::from_secs(1);
+	dir_builder.create(dirs.data())?;
+	dir_builder.create(dirs.images())?;
+	dir_builder.create(&dirs.cards)?;
+	dir_builder.create(dirs.decks())?;
+	Ok(())
+}
+
+/// Deletes a directory and everything in it, if it exists.
+pub fn remove_directory(path: &Path) -> Result<()> {
+	if path.exists() {
+		fs::remove_dir_all(path)?;
+	}
+	Ok(())
+}
+
+/// Deletes the rendered cards, leaving anything else in the cards directory alone.
+pub fn remove_cards(dirs: &Directories) -> Result<()> {
+	if !dirs.cards.exists() {
+		return Ok(());
+	}
+	for entry in fs::read_dir(&dirs.cards)? {
+		let path = entry?.path();
+		if path.is_file() && path.extension().is_some_and(|ext| ext == "png") {
+			fs::remove_file(path)?;
+		}
+	}
+	Ok(())
+}
+
+/// Deletes the rendered cards of `removed` skills, unless one of the `remaining` skills
+/// shares its card, like the PvE version of a skill that lost its PvP split.
+pub fn remove_cards_of(dirs: &Directories, removed: &[Skill], remaining: &[Skill]) -> Result<()> {
+	for skill in removed {
+		let path = dirs.card_path(skill);
+		if path.exists() && !remaining.iter().any(|other| dirs.card_path(other) == path) {
+			fs::remove_file(path)?;
+		}
+	}
+	Ok(())
+}
+
+/// Deletes a directory if it exists and there's nothing left in it.
+pub fn remove_empty_directory(path: &Path) -> Result<()> {
+	if path.is_dir() && fs::read_dir(path)?.next().is_none() {
+		fs::remove_dir(path)?;
+	}
+	Ok(())
+}
+
+/// Downloads the icons of all `skills` we don't have yet, a few at a time.
+/// Icons we couldn't get are recorded in `report`.
+pub fn build_image_cache(
+	dirs: &Directories,
+	skills: &[Skill],
+	force: bool,
+	wiki: &Wiki,
+	report: &mut Report,
+) {
+	// A few skills share an icon, so we make sure to download each one only once.
+	let mut missing: Vec<(&str, Vec<&Skill>)> = vec![];
+	let mut by_url: HashMap<&str, usize> = HashMap::new();
 	for skill in skills {
-		if fs::metadata(skill.icon_path()).is_ok() {
-			// Already exists
-			// Manually instead of filter because a few skills will have the same icon
+		if !force && dirs.icon_path(skill).exists() {
 			continue;
 		}
-		let url = &format!("https://wiki.guildwars.com{}", skill.icon_url);
-		let response = client
-			.get(url)
-			.send()
-			.expect(&format!("Couldn't send request to {}.", url));
-		let path = skill.icon_path();
-		let data = response.bytes().unwrap();
-		if path.ends_with("-Kurzick.jpg") || path.ends_with("-Luxon.jpg") {
-			let image = image::load_from_memory(&*data).unwrap();
-			let kurzick = image.crop_imm(0, 0, 64, 64);
-			let luxon = image.crop_imm(0, 64, 64, 64);
-			let path = path
-				.trim_end_matches("-Kurzick.jpg")
-				.trim_end_matches("-Luxon.jpg");
-			kurzick
-				.save(format!("{}-Kurzick.jpg", path))
-				.expect(&format!("Couldn't write to {}-Kurzick.jpg.", &path));
-			luxon
-				.save(format!("{}-Luxon.jpg", path))
-				.expect(&format!("Couldn't write to {}-Luxon.jpg.", &path));
-		} else {
-			fs::write(&path, data).expect(&format!("Couldn't write to {}.", &path));
+		match by_url.get(skill.icon_url.as_str()) {
+			Some(&idx) => missing[idx].1.push(skill),
+			None => {
+				by_url.insert(skill.icon_url.as_str(), missing.len());
+				missing.push((&skill.icon_url, vec![skill]));
+			}
+		}
+	}
+
+	let downloader = wiki.downloader();
+	let failures = downloader.run(&missing, |(url, sharing)| {
+		download_icon(wiki, dirs, url, sharing)
+	});
+	for ((_, sharing), err) in failures {
+		let names: Vec<&str> = sharing.iter().map(|skill| skill.name.as_str()).collect();
+		report.record(names.join(", "), err);
+	}
+}
+
+/// Downloads the icon at `icon_url` for all `skills` that use it.
+fn download_icon(wiki: &Wiki, dirs: &Directories, icon_url: &str, skills: &[&Skill]) -> Result<()> {
+	let url = &wiki.url(icon_url);
+	let icon = wiki.downloader().get(url)?.expect_image(url)?;
+	for skill in skills {
+		let path = dirs.icon_path(skill);
+		// Allegiance skills have their Kurzick and Luxon icons stacked on top of each other.
+		let offset = match skill.attribute {
+			Some(Attribute::KurzickRank) => Some(0),
+			Some(Attribute::LuxonRank) => Some(64),
+			_ => None,
+		};
+		match offset {
+			Some(y) => {
+				let cropped = image::load_from_memory(&icon.body)?.crop_imm(0, y, 64, 64);
+				write_atomically(&path, |temp| {
+					Ok(cropped.save_with_format(temp, ImageFormat::Jpeg)?)
+				})?;
+			}
+			None => write_atomically(&path, |temp| Ok(fs::write(temp, &icon.body)?))?,
 		}
-		thread::sleep(delay);
 	}
+	Ok(())
 }
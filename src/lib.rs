@@ -0,0 +1,30 @@
+//! Create playing cards for Guild Wars skills.
+//!
+//! Skills are scraped from the official wiki, or another [`source::SkillSource`], and cached by
+//! [`io`], rendered to card images by [`card`] using the [`assets::CardAssets`], and laid out on
+//! Tabletop Simulator deck sheets by [`tabletop`], with card backs from [`back`].
+//! The cards can be drafted with [`draft`], and builds shared as the game's template codes
+//! with [`template`].
+//! [`export`] writes the skill list to files other tools can read, and [`print`] lays out the
+//! cards on PDF pages to print at home.
+
+pub mod assets;
+pub mod back;
+pub mod card;
+pub mod details;
+pub mod diff;
+pub mod download;
+pub mod draft;
+pub mod error;
+pub mod export;
+pub mod filter;
+pub mod io;
+pub mod packs;
+pub mod print;
+pub mod skill;
+pub mod source;
+pub mod tabletop;
+pub mod template;
+
+pub use error::{Error, Report, Result};
+pub use skill::{Attribute, Campaign, GameMode, Profession, Resource, Skill, SkillType};
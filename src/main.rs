@@ -1,25 +1,475 @@
-mod card;
-mod io;
-mod skill;
-mod tabletop;
-use skill::{Profession, Skill};
+mod cli;
+use cli::{Command, Opt, Selection};
+use guild_wars_skill_cards::back::BackDesign;
+use guild_wars_skill_cards::diff::{load_snapshot, SkillDiff};
+use guild_wars_skill_cards::download::{DownloadSettings, Downloader};
+use guild_wars_skill_cards::draft::{self, DraftSettings, Turn};
+use guild_wars_skill_cards::export::export_skills;
+use guild_wars_skill_cards::io::{self, load_skills, Directories};
+use guild_wars_skill_cards::packs::PackRules;
+use guild_wars_skill_cards::print::{self, PrintSettings};
+use guild_wars_skill_cards::source::{SkillSource, Wiki, WIKI_URL};
+use guild_wars_skill_cards::tabletop::{CardOrder, Grouping, SheetLayout};
+use guild_wars_skill_cards::template::Template;
+use guild_wars_skill_cards::{card, tabletop, Error, Profession, Report, Result, Skill};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use structopt::StructOpt;
 
 fn main() {
-	io::create_directories();
+	let opt = Opt::from_args();
+	let dirs = Directories {
+		cache: opt.cache_dir,
+		cards: opt.cards_dir,
+	};
 
+	let mut report = Report::default();
+	let result = run(opt.command, &dirs, &mut report);
+
+	if !report.is_empty() {
+		eprint!("{}", report);
+	}
+	if let Err(err) = &result {
+		eprintln!("{}", err);
+	}
+	if result.is_err() || !report.is_empty() {
+		process::exit(1);
+	}
+}
+
+fn run(command: Option<Command>, dirs: &Directories, report: &mut Report) -> Result<()> {
+	match command {
+		None => {
+			let wiki = Wiki::new(WIKI_URL, Downloader::new(DownloadSettings::default())?);
+			let professions = fetch(dirs, &Selection::default(), false, &wiki, &wiki, report)?;
+			if professions.is_empty() {
+				return Ok(());
+			}
+			let selection = Selection {
+				professions,
+				..Selection::default()
+			};
+			render(dirs, &selection, false, None, report)?;
+			decks(
+				dirs,
+				&selection,
+				Grouping::All,
+				CardOrder::Id,
+				None,
+				None,
+				report,
+			)
+		}
+		Some(Command::Fetch {
+			selection,
+			force,
+			source,
+		}) => {
+			let wiki = source.wiki()?;
+			let skill_source = source.source(&wiki);
+			let professions = fetch(dirs, &selection, force, &*skill_source, &wiki, report)?;
+			if source.details {
+				for profession in professions {
+					io::build_details_cache(dirs, &wiki, profession, force, report)?;
+				}
+			}
+			Ok(())
+		}
+		Some(Command::Refresh { selection, source }) => {
+			let wiki = source.wiki()?;
+			let skill_source = source.source(&wiki);
+			refresh(
+				dirs,
+				&selection,
+				&*skill_source,
+				&wiki,
+				source.details,
+				report,
+			)
+		}
+		Some(Command::Diff { old, new, json }) => diff(&old, &new, json),
+		Some(Command::Render {
+			selection,
+			force,
+			jobs,
+			rank,
+		}) => {
+			if let Some(jobs) = jobs {
+				rayon::ThreadPoolBuilder::new()
+					.num_threads(jobs)
+					.build_global()?;
+			}
+			render(dirs, &selection, force, rank, report)
+		}
+		Some(Command::Decks {
+			selection,
+			group_by,
+			by_campaign,
+			sort_by,
+			back,
+			layout,
+		}) => decks(
+			dirs,
+			&selection,
+			match group_by {
+				Some(grouping) => grouping,
+				None if by_campaign => Grouping::Campaign,
+				None => Grouping::All,
+			},
+			sort_by,
+			back.as_deref(),
+			layout.as_deref(),
+			report,
+		),
+		Some(Command::Draft {
+			selection,
+			players,
+			real_players,
+			pack_size,
+			rounds,
+			seed,
+			pack_rules,
+			player_professions,
+			output,
+		}) => {
+			if real_players > players {
+				return Err(Error::draft(format!(
+					"{} real players don't fit at a table of {}",
+					real_players, players
+				)));
+			}
+			let settings = DraftSettings {
+				real_players,
+				fake_players: players - real_players,
+				pack_size,
+				rounds,
+				seed: seed.unwrap_or_else(rand::random),
+				rules: match pack_rules {
+					Some(path) => PackRules::load(&path)?,
+					None => PackRules::default(),
+				},
+				professions: player_professions,
+			};
+			draft(dirs, &selection, &settings, output.as_deref())
+		}
+		Some(Command::Template { code, skills, name }) => match code {
+			Some(code) => template_deck(dirs, &code, &name, report),
+			None => template_code(dirs, &skills),
+		},
+		Some(Command::Export { selection, outputs }) => export(dirs, &selection, &outputs),
+		Some(Command::Print {
+			selection,
+			output,
+			settings,
+			back,
+		}) => print(
+			dirs,
+			&selection,
+			&settings.settings(),
+			back.as_deref(),
+			&output,
+			report,
+		),
+		Some(Command::List { selection }) => list(dirs, &selection),
+		Some(Command::Clean {
+			data,
+			images,
+			cards,
+			decks,
+		}) => clean(dirs, data, images, cards, decks),
+	}
+}
+
+fn fetch(
+	dirs: &Directories,
+	selection: &Selection,
+	force: bool,
+	source: &dyn SkillSource,
+	wiki: &Wiki,
+	report: &mut Report,
+) -> Result<Vec<Profession>> {
+	io::create_directories(dirs)?;
+
+	// One profession failing shouldn't cost us the others.
+	let mut professions = vec![];
+	for profession in selection.professions() {
+		match io::build_data_cache(dirs, source, profession, force, report) {
+			Ok(()) if dirs.data_path(profession).exists() => professions.push(profession),
+			Ok(()) => {}
+			Err(err) => report.record(format!("{} skill list", profession), err),
+		}
+	}
+
+	let mut skills = load_skills(dirs, &professions, &selection.filter()?)?;
+	skills.push(Skill::hidden());
+	io::build_image_cache(dirs, &skills, force, wiki, report);
+	Ok(professions)
+}
+
+fn refresh(
+	dirs: &Directories,
+	selection: &Selection,
+	source: &dyn SkillSource,
+	wiki: &Wiki,
+	details: bool,
+	report: &mut Report,
+) -> Result<()> {
+	io::create_directories(dirs)?;
+
+	let filter = selection.filter()?;
+	let mut changed = vec![];
+	let mut moved_icons = vec![];
+	for profession in selection.professions() {
+		let update = match io::refresh_data_cache(dirs, source, profession, report) {
+			Ok(update) => update,
+			Err(err) => {
+				report.record(format!("{} skill list", profession), err);
+				continue;
+			}
+		};
+		print!("{}", update.diff);
+		if update.is_empty() {
+			continue;
+		}
+		if details {
+			// only the changed skills have lost their details
+			if let Err(err) = io::build_details_cache(dirs, wiki, profession, false, report) {
+				report.record(format!("{} skill details", profession), err);
+			}
+		}
+		let removed = io::load_skill_cache(dirs, profession)
+			.and_then(|remaining| io::remove_cards_of(dirs, &update.removed, &remaining));
+		if let Err(err) = removed {
+			report.record(format!("{} removed cards", profession), err);
+		}
+		changed.extend(update.changed);
+		moved_icons.extend(update.moved_icons);
+	}
+	changed.retain(|skill| filter.keeps(skill));
+	moved_icons.retain(|skill| filter.keeps(skill));
+
+	// the icons of all other changed skills are still good, unless we never had them
+	io::build_image_cache(dirs, &moved_icons, true, wiki, report);
+	io::build_image_cache(dirs, &changed, false, wiki, report);
+	card::generate_cards(&changed, dirs, true, report)
+}
+
+fn diff(old: &Path, new: &Path, json: bool) -> Result<()> {
+	let diff = SkillDiff::between(&load_snapshot(old)?, &load_snapshot(new)?);
+	if json {
+		println!("{}", serde_json::to_string_pretty(&diff)?);
+	} else if diff.is_empty() {
+		println!("No changes.");
+	} else {
+		print!("{}", diff);
+	}
+	Ok(())
+}
+
+fn render(
+	dirs: &Directories,
+	selection: &Selection,
+	force: bool,
+	rank: Option<u8>,
+	report: &mut Report,
+) -> Result<()> {
+	io::create_directories(dirs)?;
+
+	let mut skills = load_skills(dirs, &selection.professions(), &selection.filter()?)?;
+	if let Some(rank) = rank {
+		for skill in &mut skills {
+			skill.description = skill.description_at(rank);
+		}
+	}
+	skills.push(Skill::hidden());
+	card::generate_cards(&skills, dirs, force, report)
+}
+
+fn decks(
+	dirs: &Directories,
+	selection: &Selection,
+	grouping: Grouping,
+	order: CardOrder,
+	back: Option<&Path>,
+	layout: Option<&Path>,
+	report: &mut Report,
+) -> Result<()> {
+	io::create_directories(dirs)?;
+
+	let backs = match back {
+		Some(path) => BackDesign::load(path)?,
+		None => BackDesign::default(),
+	};
+	let layout = match layout {
+		Some(path) => SheetLayout::load(path)?,
+		None => SheetLayout::default(),
+	};
+	let skills = load_skills(dirs, &selection.professions(), &selection.filter()?)?;
+	tabletop::create_grouped_decks(&skills, grouping, order, dirs, &layout, &backs, report)
+}
+
+fn draft(
+	dirs: &Directories,
+	selection: &Selection,
+	settings: &DraftSettings,
+	output: Option<&Path>,
+) -> Result<()> {
+	let pool = load_skills(dirs, &selection.professions(), &selection.filter()?)?;
+	println!("Seed: {}", settings.seed);
+	let result = draft::run_draft(pool, settings, pick_card)?;
+
+	for pool in result.pools.iter().filter(|pool| !pool.is_fake) {
+		println!();
+		println!("Player {} drafted:", pool.player + 1);
+		for skill in &pool.picks {
+			println!("  {} — {}", skill.variant_name(), skill.type_line());
+		}
+	}
+	if let Some(output) = output {
+		fs::write(output, serde_json::to_string_pretty(&result)?)?;
+	}
+	Ok(())
+}
+
+/// Asks a real player which card they want, until they give us a number that's in the pack.
+fn pick_card(turn: &Turn) -> Result<usize> {
+	println!();
+	println!(
+		"Round {}, player {}, pick {}:",
+		turn.round,
+		turn.player + 1,
+		turn.pool.len() + 1
+	);
+	for (idx, skill) in turn.pack.iter().enumerate() {
+		println!(
+			"  {:>2}. {} — {}",
+			idx + 1,
+			skill.variant_name(),
+			skill.type_line()
+		);
+	}
+
+	let stdin = std::io::stdin();
+	loop {
+		print!("Your pick: ");
+		std::io::stdout().flush()?;
+		let mut line = String::new();
+		if stdin.lock().read_line(&mut line)? == 0 {
+			return Err(Error::draft("Ran out of picks"));
+		}
+		match line.trim().parse::<usize>() {
+			Ok(pick) if (1..=turn.pack.len()).contains(&pick) => return Ok(pick - 1),
+			_ => println!("Pick a number from 1 to {}.", turn.pack.len()),
+		}
+	}
+}
+
+/// Every skill we have cached, including the PvP versions of split skills.
+fn all_skills(dirs: &Directories) -> Result<Vec<Skill>> {
+	let mut skills = vec![];
 	for profession in Profession::iter() {
-		io::build_data_cache(profession);
+		skills.extend(io::load_skill_cache(dirs, profession)?);
 	}
+	Ok(skills)
+}
 
-	let skills: Vec<Skill> = Profession::iter()
-		.flat_map(|profession| io::load_skill_cache(profession))
-		.filter(|s: &Skill| !s.is_pvp_variant())
-		.collect();
-	io::build_image_cache(&skills);
-	io::build_image_cache(&[Skill::hidden()]);
-	for skill in &skills {
-		card::generate_card(&skill);
+fn template_deck(dirs: &Directories, code: &str, name: &str, report: &mut Report) -> Result<()> {
+	io::create_directories(dirs)?;
+
+	let template = Template::decode(code)?;
+	let pool = all_skills(dirs)?;
+	let hand = template.skills_in(&pool)?;
+	match template.secondary {
+		Some(secondary) => println!("{}/{}", template.primary, secondary),
+		None => println!("{}", template.primary),
+	}
+	for (attribute, rank) in &template.attributes {
+		println!("  {} {}", attribute, rank);
+	}
+	for skill in &hand {
+		println!("{} — {}", skill.variant_name(), skill.type_line());
+	}
+	let sheet = tabletop::create_hand_sheet(
+		&hand,
+		name,
+		dirs,
+		&SheetLayout::default(),
+		&BackDesign::default(),
+		report,
+	)?;
+	println!("Saved to {}", sheet.display());
+	Ok(())
+}
+
+fn template_code(dirs: &Directories, names: &[String]) -> Result<()> {
+	let pool = all_skills(dirs)?;
+	let hand = names
+		.iter()
+		.map(|name| {
+			pool.iter()
+				.find(|skill| skill.variant_name() == *name)
+				.cloned()
+				.ok_or_else(|| {
+					Error::template(format!("No skill called {} in the card pool", name))
+				})
+		})
+		.collect::<Result<Vec<Skill>>>()?;
+	println!("{}", Template::from_hand(&hand)?.encode()?);
+	Ok(())
+}
+
+fn export(dirs: &Directories, selection: &Selection, outputs: &[PathBuf]) -> Result<()> {
+	let skills = load_skills(dirs, &selection.professions(), &selection.filter()?)?;
+	for path in outputs {
+		export_skills(&skills, path)?;
+		println!("Wrote {} skills to {}", skills.len(), path.display());
+	}
+	Ok(())
+}
+
+fn print(
+	dirs: &Directories,
+	selection: &Selection,
+	settings: &PrintSettings,
+	back: Option<&Path>,
+	output: &Path,
+	report: &mut Report,
+) -> Result<()> {
+	let backs = match back {
+		Some(path) => BackDesign::load(path)?,
+		None => BackDesign::default(),
+	};
+	let skills = load_skills(dirs, &selection.professions(), &selection.filter()?)?;
+	let pages = print::create_print_and_play(&skills, dirs, settings, &backs, output, report)?;
+	println!("Wrote {} pages to {}", pages, output.display());
+	Ok(())
+}
+
+fn list(dirs: &Directories, selection: &Selection) -> Result<()> {
+	for skill in load_skills(dirs, &selection.professions(), &selection.filter()?)? {
+		println!("{} — {}", skill.name, skill.type_line());
+	}
+	Ok(())
+}
+
+fn clean(dirs: &Directories, data: bool, images: bool, cards: bool, decks: bool) -> Result<()> {
+	let everything = !(data || images || cards || decks);
+	if everything || data {
+		io::remove_directory(&dirs.data())?;
+	}
+	if everything || images {
+		io::remove_directory(&dirs.images())?;
+	}
+	if everything || cards || decks {
+		io::remove_directory(&dirs.decks())?;
+	}
+	if everything || cards {
+		io::remove_cards(dirs)?;
 	}
-	card::generate_card(&Skill::hidden());
-	tabletop::create_tabletop_simulator_decks(&skills);
+	// the roots may well be shared with other files, so only drop them once they're empty
+	io::remove_empty_directory(&dirs.cache)?;
+	io::remove_empty_directory(&dirs.cards)?;
+	Ok(())
 }
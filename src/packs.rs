@@ -0,0 +1,285 @@
+use crate::error::{Error, Result};
+use crate::skill::{Profession, Skill};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// What goes into a booster pack, on top of the number of cards.
+///
+/// Read from a TOML file like this one:
+///
+/// ```toml
+/// max_elites = 1
+/// max_pve_only = 2
+/// min_own_profession = 3
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackRules {
+	/// At most this many elite skills in a pack.
+	pub max_elites: Option<usize>,
+	/// At most this many PvE-only skills in a pack.
+	pub max_pve_only: Option<usize>,
+	/// At least this many skills of the profession of the player who opens the pack,
+	/// if they have one.
+	#[serde(default)]
+	pub min_own_profession: usize,
+}
+
+impl PackRules {
+	pub fn load(path: &Path) -> Result<Self> {
+		Ok(toml::from_str(&fs::read_to_string(path)?)?)
+	}
+
+	/// Makes sure we can deal packs of `pack_size` from `pool` under these rules, by planning
+	/// them without taking anything. If this passes, so does [`PackRules::deal_packs`].
+	/// `professions` has an entry for every pack we'll deal, the profession of the player opening it.
+	pub fn validate(
+		&self,
+		pool: &[Skill],
+		pack_size: usize,
+		professions: &[Option<Profession>],
+	) -> Result<()> {
+		self.deal_packs(&mut pool.to_vec(), pack_size, professions)
+			.map(|_| ())
+	}
+
+	/// Takes a pack of `pack_size` cards for a player of `profession` out of `pool`,
+	/// preferring the cards at its front.
+	pub fn deal_pack(
+		&self,
+		pool: &mut Vec<Skill>,
+		pack_size: usize,
+		profession: Option<Profession>,
+	) -> Result<Vec<Skill>> {
+		Ok(self.deal_packs(pool, pack_size, &[profession])?.remove(0))
+	}
+
+	/// Takes a pack of `pack_size` cards out of `pool` for each entry of `professions`,
+	/// the profession of the player opening it, preferring the cards at the front of `pool`.
+	///
+	/// All packs are planned before any is dealt: first the skills of the players' own
+	/// professions, so no other pack uses them up, then the rest. Either every pack follows
+	/// the rules, or nothing is taken out of `pool` and we get an error.
+	pub fn deal_packs(
+		&self,
+		pool: &mut Vec<Skill>,
+		pack_size: usize,
+		professions: &[Option<Profession>],
+	) -> Result<Vec<Vec<Skill>>> {
+		self.check_pool(pool, pack_size, professions)?;
+		// Taking the cards at the front keeps the packs as random as the pool. When the pool
+		// is too tight for that, we try again placing elite and PvE-only skills first.
+		let plan = self
+			.plan(pool, pack_size, professions, false)
+			.or_else(|_| self.plan(pool, pack_size, professions, true))?;
+
+		let mut cards: Vec<Option<Skill>> = pool.drain(..).map(Some).collect();
+		let packs = plan
+			.packs
+			.into_iter()
+			.map(|pack| {
+				let mut indices = pack.cards;
+				indices.sort_unstable();
+				indices
+					.into_iter()
+					.filter_map(|idx| cards[idx].take())
+					.collect()
+			})
+			.collect();
+		*pool = cards.into_iter().flatten().collect();
+		Ok(packs)
+	}
+
+	/// Picks the cards of every pack from `pool`, first the skills of the players' own
+	/// professions, then the rest. With `careful`, packs take the elite and PvE-only skills
+	/// that fit before any other, keeping the others for the slots the caps leave to them.
+	fn plan(
+		&self,
+		pool: &[Skill],
+		pack_size: usize,
+		professions: &[Option<Profession>],
+		careful: bool,
+	) -> Result<Plan> {
+		let mut plan = Plan {
+			packs: vec![PackPlan::default(); professions.len()],
+			taken: vec![false; pool.len()],
+			unrestricted_left: pool.iter().filter(|s| !self.is_restricted(s)).count(),
+		};
+		// one card per pack at a time, so the packs share what's there evenly
+		for _ in 0..self.min_own_profession {
+			for (pack, profession) in professions.iter().enumerate() {
+				if let Some(profession) = *profession {
+					let idx = self
+						.next_card(pool, &plan, pack, pack_size, careful, |s| {
+							s.profession == profession
+						})
+						.ok_or_else(|| {
+							Error::draft(format!(
+								"Ran out of {} skills to put in packs",
+								profession
+							))
+						})?;
+					plan.take(self, pool, pack, idx);
+				}
+			}
+		}
+		for _ in 0..pack_size {
+			for pack in 0..professions.len() {
+				if plan.packs[pack].cards.len() == pack_size {
+					continue;
+				}
+				let idx = self
+					.next_card(pool, &plan, pack, pack_size, careful, |_| true)
+					.ok_or_else(|| Error::draft("Ran out of skills that fit the pack rules"))?;
+				plan.take(self, pool, pack, idx);
+			}
+		}
+		Ok(plan)
+	}
+
+	/// Quick checks with clearer errors than running out of cards halfway through planning.
+	fn check_pool(
+		&self,
+		pool: &[Skill],
+		pack_size: usize,
+		professions: &[Option<Profession>],
+	) -> Result<()> {
+		let packs = professions.len();
+		if self.min_own_profession > pack_size {
+			return Err(Error::draft(format!(
+				"Packs of {} can't hold {} skills of the player's profession",
+				pack_size, self.min_own_profession
+			)));
+		}
+		if packs * pack_size > pool.len() {
+			return Err(Error::draft(format!(
+				"Only {} cards in the pool, not enough for {} packs of {}",
+				pool.len(),
+				packs,
+				pack_size
+			)));
+		}
+
+		let mut checked = vec![];
+		for profession in professions.iter().flatten() {
+			if checked.contains(profession) {
+				continue;
+			}
+			checked.push(*profession);
+			let needed = professions
+				.iter()
+				.filter(|&&p| p == Some(*profession))
+				.count() * self.min_own_profession;
+			let available = pool.iter().filter(|s| s.profession == *profession).count();
+			if needed > available {
+				return Err(Error::draft(format!(
+					"Only {} {} skills in the pool, but the packs need {}",
+					available, profession, needed
+				)));
+			}
+		}
+
+		let needed = packs * self.unrestricted_needed(&PackPlan::default(), pack_size);
+		let available = pool.iter().filter(|s| !self.is_restricted(s)).count();
+		if needed > available {
+			return Err(Error::draft(format!(
+				"Only {} skills in the pool are neither elite nor PvE-only, but the packs need {}",
+				available, needed
+			)));
+		}
+		Ok(())
+	}
+
+	/// The first card left in `pool` that `wanted` accepts for `pack` and the caps leave room for,
+	/// as long as that leaves enough unrestricted skills for every pack to fill up.
+	/// With `careful`, elite and PvE-only skills come before the others.
+	fn next_card(
+		&self,
+		pool: &[Skill],
+		plan: &Plan,
+		pack: usize,
+		pack_size: usize,
+		careful: bool,
+		wanted: impl Fn(&Skill) -> bool,
+	) -> Option<usize> {
+		let current = &plan.packs[pack];
+		let needed_elsewhere = plan
+			.packs
+			.iter()
+			.map(|pack| self.unrestricted_needed(pack, pack_size))
+			.sum::<usize>()
+			- self.unrestricted_needed(current, pack_size);
+		let usable = |idx: &usize| {
+			let skill = &pool[*idx];
+			if plan.taken[*idx] || !wanted(skill) || !self.fits(current, skill) {
+				return false;
+			}
+			let mut after = current.clone();
+			after.add(*idx, skill);
+			let unrestricted_left = plan.unrestricted_left - !self.is_restricted(skill) as usize;
+			unrestricted_left >= needed_elsewhere + self.unrestricted_needed(&after, pack_size)
+		};
+		careful
+			.then(|| (0..pool.len()).find(|idx| self.is_restricted(&pool[*idx]) && usable(idx)))
+			.flatten()
+			.or_else(|| (0..pool.len()).find(usable))
+	}
+
+	fn fits(&self, pack: &PackPlan, skill: &Skill) -> bool {
+		let elite = matches!(self.max_elites, Some(max) if skill.is_elite && pack.elites >= max);
+		let pve =
+			matches!(self.max_pve_only, Some(max) if skill.is_pve_only() && pack.pve_only >= max);
+		!(elite || pve)
+	}
+
+	/// How many of the open slots of `pack` need a skill that's neither elite nor PvE-only,
+	/// because the caps don't leave them to elite or PvE-only skills.
+	fn unrestricted_needed(&self, pack: &PackPlan, pack_size: usize) -> usize {
+		let open = pack_size - pack.cards.len();
+		let elites = self
+			.max_elites
+			.map_or(0, |max| max.saturating_sub(pack.elites));
+		let pve_only = self
+			.max_pve_only
+			.map_or(0, |max| max.saturating_sub(pack.pve_only));
+		open.saturating_sub(elites + pve_only)
+	}
+
+	fn is_restricted(&self, skill: &Skill) -> bool {
+		(skill.is_elite && self.max_elites.is_some())
+			|| (skill.is_pve_only() && self.max_pve_only.is_some())
+	}
+}
+
+/// The packs we're about to deal, by the indices of their cards in the pool.
+struct Plan {
+	packs: Vec<PackPlan>,
+	/// Whether the card at each index of the pool went into a pack.
+	taken: Vec<bool>,
+	/// Cards left in the pool that are neither elite nor PvE-only under the caps.
+	unrestricted_left: usize,
+}
+
+impl Plan {
+	fn take(&mut self, rules: &PackRules, pool: &[Skill], pack: usize, idx: usize) {
+		self.taken[idx] = true;
+		self.unrestricted_left -= !rules.is_restricted(&pool[idx]) as usize;
+		self.packs[pack].add(idx, &pool[idx]);
+	}
+}
+
+#[derive(Debug, Clone, Default)]
+struct PackPlan {
+	cards: Vec<usize>,
+	elites: usize,
+	pve_only: usize,
+}
+
+impl PackPlan {
+	fn add(&mut self, idx: usize, skill: &Skill) {
+		self.cards.push(idx);
+		self.elites += skill.is_elite as usize;
+		self.pve_only += skill.is_pve_only() as usize;
+	}
+}
@@ -0,0 +1,532 @@
+use crate::assets::CardAssets;
+use crate::back::BackDesign;
+use crate::download::write_atomically;
+use crate::error::{Error, Report, Result};
+use crate::io::Directories;
+use crate::skill::Skill;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::{self, FilterType};
+use image::{ColorType, RgbaImage};
+use printpdf::{
+	Color, ColorBits, ColorSpace, Image, ImageFilter, ImageTransform, ImageXObject, Line, Mm,
+	PdfDocument, PdfLayerReference, Point, Px, Rgb,
+};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::{fmt, str};
+
+const MM_PER_INCH: f64 = 25.4;
+/// Cut marks are this long, or shorter if the margin around the cards is too narrow.
+const CUT_MARK_LENGTH: f64 = 5.0;
+/// Cut marks shorter than this are hard to cut by, so the grid leaves room for at least this much.
+const MIN_CUT_MARK_LENGTH: f64 = 2.0;
+/// Space between the cards and the start of a cut mark, so the marks don't show on the cards.
+const CUT_MARK_GAP: f64 = 1.0;
+/// Quality of the JPEGs we embed the cards as. Lossless would make the PDF huge.
+const JPEG_QUALITY: u8 = 92;
+
+/// The paper we print on.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Paper {
+	A4,
+	Letter,
+}
+
+impl Paper {
+	const ALL: [Self; 2] = [Self::A4, Self::Letter];
+
+	pub fn iter() -> impl Iterator<Item = Self> {
+		Self::ALL.iter().copied()
+	}
+
+	fn name(self) -> &'static str {
+		match self {
+			Self::A4 => "A4",
+			Self::Letter => "Letter",
+		}
+	}
+
+	/// Width and height of a page in millimeters, upright.
+	pub fn size(self) -> (f64, f64) {
+		match self {
+			Self::A4 => (210.0, 297.0),
+			Self::Letter => (215.9, 279.4),
+		}
+	}
+}
+
+impl str::FromStr for Paper {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		Self::iter()
+			.find(|paper| paper.name().eq_ignore_ascii_case(s))
+			.ok_or_else(|| format!("Can't print on {} paper, only on A4 or Letter", s))
+	}
+}
+
+impl fmt::Display for Paper {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.name())
+	}
+}
+
+/// How big the printed cards are, to fit the sleeves of real playing cards.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CardSize {
+	/// 63.5 by 88.9 millimeters, like most card games.
+	Poker,
+	/// 70 by 120 millimeters, with more room for long descriptions.
+	Tarot,
+}
+
+impl CardSize {
+	const ALL: [Self; 2] = [Self::Poker, Self::Tarot];
+
+	pub fn iter() -> impl Iterator<Item = Self> {
+		Self::ALL.iter().copied()
+	}
+
+	fn name(self) -> &'static str {
+		match self {
+			Self::Poker => "poker",
+			Self::Tarot => "tarot",
+		}
+	}
+
+	/// Width and height of a card in millimeters, without bleed.
+	pub fn size(self) -> (f64, f64) {
+		match self {
+			Self::Poker => (63.5, 88.9),
+			Self::Tarot => (70.0, 120.0),
+		}
+	}
+}
+
+impl str::FromStr for CardSize {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		Self::iter()
+			.find(|size| size.name().eq_ignore_ascii_case(s))
+			.ok_or_else(|| format!("Can't print {} sized cards, only poker or tarot", s))
+	}
+}
+
+impl fmt::Display for CardSize {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.name())
+	}
+}
+
+/// How cards are laid out on the pages of a print-and-play PDF.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintSettings {
+	pub paper: Paper,
+	pub card_size: CardSize,
+	/// Millimeters of extra card around each edge, so cutting a little off doesn't leave
+	/// a white border. The card's edges are stretched into it.
+	pub bleed: f64,
+	/// Millimeters along the edges of the page that stay empty, because most printers
+	/// can't print all the way to the edge. Cut marks go here.
+	pub margin: f64,
+	/// Draw short lines in the margin that show where to cut.
+	pub cut_marks: bool,
+	/// Follow each page of cards with a page of their backs, mirrored to line up with the
+	/// fronts when printed on both sides and flipped on the long edge.
+	pub duplex: bool,
+	/// Resolution the cards are embedded at.
+	pub dpi: u32,
+}
+
+impl Default for PrintSettings {
+	fn default() -> Self {
+		Self {
+			paper: Paper::A4,
+			card_size: CardSize::Poker,
+			bleed: 0.0,
+			margin: 5.0,
+			cut_marks: true,
+			duplex: false,
+			dpi: 300,
+		}
+	}
+}
+
+impl PrintSettings {
+	/// Width and height of a card in millimeters, including bleed.
+	fn cell_size(&self) -> (f64, f64) {
+		let (width, height) = self.card_size.size();
+		(width + 2.0 * self.bleed, height + 2.0 * self.bleed)
+	}
+
+	/// Columns and rows of cards that fit on a page inside the margins, leaving room for
+	/// cut marks if we draw them. Errors if the settings don't make sense, or not even one card fits.
+	pub fn grid(&self) -> Result<(u32, u32)> {
+		if !(self.bleed >= 0.0 && self.margin >= 0.0) {
+			return Err(Error::print("Bleed and margin can't be negative"));
+		}
+		if !(72..=1200).contains(&self.dpi) {
+			return Err(Error::print(format!(
+				"Cards are printed at 72 to 1200 DPI, not {}",
+				self.dpi
+			)));
+		}
+		let (page_width, page_height) = self.paper.size();
+		let (cell_width, cell_height) = self.cell_size();
+		let border = if self.cut_marks {
+			self.margin + CUT_MARK_GAP + MIN_CUT_MARK_LENGTH
+		} else {
+			self.margin
+		};
+		let columns = ((page_width - 2.0 * border) / cell_width).floor().max(0.0) as u32;
+		let rows = ((page_height - 2.0 * border) / cell_height)
+			.floor()
+			.max(0.0) as u32;
+		if columns == 0 || rows == 0 {
+			return Err(Error::print(format!(
+				"Not a single {} card with {} mm bleed fits on {} paper with {} mm margins{}",
+				self.card_size,
+				self.bleed,
+				self.paper,
+				self.margin,
+				if self.cut_marks { " and cut marks" } else { "" }
+			)));
+		}
+		Ok((columns, rows))
+	}
+
+	/// Cards on each page.
+	pub fn cards_per_page(&self) -> Result<usize> {
+		let (columns, rows) = self.grid()?;
+		Ok((columns * rows) as usize)
+	}
+
+	fn pixels(&self, mm: f64) -> u32 {
+		(mm * f64::from(self.dpi) / MM_PER_INCH).round() as u32
+	}
+}
+
+/// Where the cards go on a page, in millimeters from its bottom left corner, like PDFs count.
+struct PageLayout {
+	columns: u32,
+	rows: u32,
+	/// Bottom left corner of the grid of cards.
+	left: f64,
+	bottom: f64,
+	cell_width: f64,
+	cell_height: f64,
+}
+
+impl PageLayout {
+	fn new(settings: &PrintSettings) -> Result<Self> {
+		let (columns, rows) = settings.grid()?;
+		let (page_width, page_height) = settings.paper.size();
+		let (cell_width, cell_height) = settings.cell_size();
+		Ok(Self {
+			columns,
+			rows,
+			left: (page_width - f64::from(columns) * cell_width) / 2.0,
+			bottom: (page_height - f64::from(rows) * cell_height) / 2.0,
+			cell_width,
+			cell_height,
+		})
+	}
+
+	/// Bottom left corner of the card in slot `index`, counted row by row from the top left.
+	/// Backs count from the top right instead, so they end up behind their fronts.
+	fn position(&self, index: usize, mirrored: bool) -> (f64, f64) {
+		let mut column = index as u32 % self.columns;
+		let row = index as u32 / self.columns;
+		if mirrored {
+			column = self.columns - 1 - column;
+		}
+		(
+			self.left + f64::from(column) * self.cell_width,
+			self.bottom + f64::from(self.rows - 1 - row) * self.cell_height,
+		)
+	}
+
+	/// Lines in the margins around the grid, in line with every edge a card is cut along,
+	/// each from the end nearest the cards.
+	fn cut_marks(&self, settings: &PrintSettings) -> Vec<((f64, f64), (f64, f64))> {
+		let (page_width, page_height) = settings.paper.size();
+		let (card_width, card_height) = settings.card_size.size();
+		let right = page_width - self.left;
+		let top = page_height - self.bottom;
+		// The marks stay inside the margins of the page, like the cards. The grid leaves them
+		// at least `MIN_CUT_MARK_LENGTH`.
+		let horizontal = (self.left - settings.margin - CUT_MARK_GAP).min(CUT_MARK_LENGTH);
+		let vertical = (self.bottom - settings.margin - CUT_MARK_GAP).min(CUT_MARK_LENGTH);
+
+		let mut marks = vec![];
+		for column in 0..self.columns {
+			let x = self.left + f64::from(column) * self.cell_width + settings.bleed;
+			for &x in &[x, x + card_width] {
+				marks.push((
+					(x, self.bottom - CUT_MARK_GAP),
+					(x, self.bottom - CUT_MARK_GAP - vertical),
+				));
+				marks.push(((x, top + CUT_MARK_GAP), (x, top + CUT_MARK_GAP + vertical)));
+			}
+		}
+		for row in 0..self.rows {
+			let y = self.bottom + f64::from(row) * self.cell_height + settings.bleed;
+			for &y in &[y, y + card_height] {
+				marks.push((
+					(self.left - CUT_MARK_GAP, y),
+					(self.left - CUT_MARK_GAP - horizontal, y),
+				));
+				marks.push((
+					(right + CUT_MARK_GAP, y),
+					(right + CUT_MARK_GAP + horizontal, y),
+				));
+			}
+		}
+		marks
+	}
+}
+
+/// Lays out the rendered cards of `skills` on the pages of a PDF at `path`, at the real size
+/// of playing cards, ready to print and cut. Returns how many pages that took.
+/// Skills without a rendered card are recorded in `report` and left out.
+pub fn create_print_and_play(
+	skills: &[Skill],
+	dirs: &Directories,
+	settings: &PrintSettings,
+	backs: &BackDesign,
+	path: &Path,
+	report: &mut Report,
+) -> Result<usize> {
+	let layout = PageLayout::new(settings)?;
+	let per_page = settings.cards_per_page()?;
+	let back = if settings.duplex {
+		let skills = skills.iter().collect::<Vec<_>>();
+		let back = backs.render(backs.back_for(&skills), &CardAssets::load()?)?;
+		Some(print_image(&back, settings)?)
+	} else {
+		None
+	};
+
+	let (page_width, page_height) = settings.paper.size();
+	let title = path.file_stem().map_or_else(
+		|| "Skill Cards".to_owned(),
+		|stem| stem.to_string_lossy().into_owned(),
+	);
+	let (doc, page, layer) = PdfDocument::new(title, Mm(page_width), Mm(page_height), "Cards");
+	let mut first_page = Some(doc.get_page(page).get_layer(layer));
+	let mut pages = 1;
+	let mut new_page = |name: &str| {
+		pages += 1;
+		let (page, layer) = doc.add_page(Mm(page_width), Mm(page_height), name);
+		doc.get_page(page).get_layer(layer)
+	};
+
+	let mut cards = Vec::with_capacity(per_page);
+	let mut skills = skills.iter().peekable();
+	while let Some(skill) = skills.next() {
+		match open_card(dirs, skill, settings) {
+			Ok(card) => cards.push(card),
+			Err(err) => report.record(&skill.name, err),
+		}
+		if cards.len() == per_page || (skills.peek().is_none() && !cards.is_empty()) {
+			let front = first_page.take().unwrap_or_else(|| new_page("Cards"));
+			let count = cards.len();
+			for (index, card) in cards.drain(..).enumerate() {
+				place(&front, card, layout.position(index, false), settings);
+			}
+			if settings.cut_marks {
+				draw_cut_marks(&front, &layout, settings);
+			}
+			if let Some(back) = &back {
+				let layer = new_page("Backs");
+				for index in 0..count {
+					place(&layer, back.clone(), layout.position(index, true), settings);
+				}
+			}
+		}
+	}
+
+	write_atomically(path, |temp| {
+		let mut file = BufWriter::new(File::create(temp)?);
+		doc.save(&mut file)
+			.map_err(|err| Error::print(err.to_string()))
+	})?;
+	Ok(pages)
+}
+
+fn open_card(dirs: &Directories, skill: &Skill, settings: &PrintSettings) -> Result<ImageXObject> {
+	print_image(&image::open(dirs.card_path(skill))?.into_rgba8(), settings)
+}
+
+/// Scales a card to its printed size at the settings' DPI and stretches its edges into the
+/// bleed, then compresses it for the PDF. Cards that aren't shaped like the printed ones keep
+/// their shape, and their edges fill the rest.
+fn print_image(card: &RgbaImage, settings: &PrintSettings) -> Result<ImageXObject> {
+	let (width, height) = settings.card_size.size();
+	let (cell_width, cell_height) = settings.cell_size();
+	let scale = (f64::from(settings.pixels(width)) / f64::from(card.width()))
+		.min(f64::from(settings.pixels(height)) / f64::from(card.height()));
+	let scaled = imageops::resize(
+		card,
+		((f64::from(card.width()) * scale).round() as u32).max(1),
+		((f64::from(card.height()) * scale).round() as u32).max(1),
+		FilterType::Lanczos3,
+	);
+
+	let (image_width, image_height) = (settings.pixels(cell_width), settings.pixels(cell_height));
+	let left = (i64::from(image_width) - i64::from(scaled.width())) / 2;
+	let top = (i64::from(image_height) - i64::from(scaled.height())) / 2;
+	let mut rgb = Vec::with_capacity((image_width * image_height * 3) as usize);
+	for y in 0..image_height {
+		let y = (i64::from(y) - top).clamp(0, i64::from(scaled.height()) - 1) as u32;
+		for x in 0..image_width {
+			let x = (i64::from(x) - left).clamp(0, i64::from(scaled.width()) - 1) as u32;
+			rgb.extend_from_slice(&scaled.get_pixel(x, y).0[..3]);
+		}
+	}
+
+	let mut jpeg = Vec::new();
+	JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY).encode(
+		&rgb,
+		image_width,
+		image_height,
+		ColorType::Rgb8,
+	)?;
+	Ok(ImageXObject {
+		width: Px(image_width as usize),
+		height: Px(image_height as usize),
+		color_space: ColorSpace::Rgb,
+		bits_per_component: ColorBits::Bit8,
+		interpolate: false,
+		image_data: jpeg,
+		image_filter: Some(ImageFilter::DCT),
+		clipping_bbox: None,
+	})
+}
+
+/// Puts `card` on the page with its bottom left corner at `(x, y)`.
+fn place(
+	layer: &PdfLayerReference,
+	card: ImageXObject,
+	(x, y): (f64, f64),
+	settings: &PrintSettings,
+) {
+	Image::from(card).add_to_layer(
+		layer.clone(),
+		ImageTransform {
+			translate_x: Some(Mm(x)),
+			translate_y: Some(Mm(y)),
+			dpi: Some(f64::from(settings.dpi)),
+			..Default::default()
+		},
+	);
+}
+
+/// Draws the cut marks of `layout` as thin black lines.
+fn draw_cut_marks(layer: &PdfLayerReference, layout: &PageLayout, settings: &PrintSettings) {
+	layer.set_outline_color(Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)));
+	layer.set_outline_thickness(0.25);
+	for (from, to) in layout.cut_marks(settings) {
+		layer.add_shape(Line {
+			points: vec![
+				(Point::new(Mm(from.0), Mm(from.1)), false),
+				(Point::new(Mm(to.0), Mm(to.1)), false),
+			],
+			is_closed: false,
+			has_fill: false,
+			has_stroke: true,
+			is_clipping_path: false,
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_close((x, y): (f64, f64), (expected_x, expected_y): (f64, f64)) {
+		assert!(
+			(x - expected_x).abs() < 1e-9 && (y - expected_y).abs() < 1e-9,
+			"({}, {}) isn't ({}, {})",
+			x,
+			y,
+			expected_x,
+			expected_y
+		);
+	}
+
+	#[test]
+	fn puts_backs_behind_their_fronts_on_a_partial_page() {
+		let settings = PrintSettings::default();
+		let layout = PageLayout::new(&settings).unwrap();
+		let (page_width, _) = settings.paper.size();
+		let (left, bottom) = (layout.left, layout.bottom);
+		let (width, height) = (layout.cell_width, layout.cell_height);
+
+		// 4 cards on a 3 by 3 page: a full top row, and one card below it
+		let backs: Vec<_> = (0..4).map(|index| layout.position(index, true)).collect();
+		assert_close(backs[0], (left + 2.0 * width, bottom + 2.0 * height));
+		assert_close(backs[1], (left + width, bottom + 2.0 * height));
+		assert_close(backs[2], (left, bottom + 2.0 * height));
+		assert_close(backs[3], (left + 2.0 * width, bottom + height));
+
+		// flipped on the long edge, every back ends up right behind its front
+		for (index, back) in backs.into_iter().enumerate() {
+			let front = layout.position(index, false);
+			assert_close((page_width - back.0 - width, back.1), front);
+		}
+	}
+
+	#[test]
+	fn lines_cut_marks_up_with_the_edges_of_the_cards() {
+		let settings = PrintSettings {
+			bleed: 3.0,
+			..PrintSettings::default()
+		};
+		let layout = PageLayout::new(&settings).unwrap();
+		// poker cards are 63.5 by 88.9 mm, and 69.5 by 94.9 with the bleed. A third row would
+		// fit, but leave the marks above and below the grid 0.15 mm long.
+		assert_eq!((layout.columns, layout.rows), (2, 2));
+		assert_close((layout.left, layout.bottom), (35.5, 53.6));
+		let (page_width, page_height) = settings.paper.size();
+
+		let marks = layout.cut_marks(&settings);
+		let mut xs: Vec<f64> = marks
+			.iter()
+			.filter(|(from, to)| from.0 == to.0)
+			.map(|(from, _)| from.0)
+			.collect();
+		xs.dedup();
+		let mut ys: Vec<f64> = marks
+			.iter()
+			.filter(|(from, to)| from.1 == to.1)
+			.map(|(from, _)| from.1)
+			.collect();
+		ys.dedup();
+		let expected_xs = [38.5, 102.0, 108.0, 171.5];
+		let expected_ys = [56.6, 145.5, 151.5, 240.4];
+		assert_eq!(xs.len(), expected_xs.len());
+		for (&x, &expected) in xs.iter().zip(&expected_xs) {
+			assert_close((x, 0.0), (expected, 0.0));
+		}
+		assert_eq!(ys.len(), expected_ys.len());
+		for (&y, &expected) in ys.iter().zip(&expected_ys) {
+			assert_close((0.0, y), (0.0, expected));
+		}
+
+		// every mark is long enough to cut by, and stays between the margin and the cards
+		let right = page_width - layout.left;
+		let top = page_height - layout.bottom;
+		for (from, to) in marks {
+			let length = (to.0 - from.0).abs() + (to.1 - from.1).abs();
+			assert!((MIN_CUT_MARK_LENGTH..=CUT_MARK_LENGTH).contains(&length));
+			for &(x, y) in &[from, to] {
+				assert!(x >= settings.margin && x <= page_width - settings.margin);
+				assert!(y >= settings.margin && y <= page_height - settings.margin);
+				assert!(x < layout.left || x > right || y < layout.bottom || y > top);
+			}
+		}
+	}
+}
@@ -1,9 +1,10 @@
+use crate::details::{Progression, SkillDetails};
+use crate::error::{Error, Result};
 use regex::{Captures, Regex};
 use scraper::{element_ref::ElementRef, Selector};
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
-use std::{fmt, iter};
-use string_builder;
+use std::{fmt, iter, str};
 
 mod helpers {
 	use super::*;
@@ -16,87 +17,92 @@ mod helpers {
 		}
 	}
 
-	pub fn determine_profession(row: ElementRef) -> Profession {
-		let style = row.value().attr("style").unwrap();
+	pub fn determine_profession(row: ElementRef) -> Result<Profession> {
+		let style = row
+			.value()
+			.attr("style")
+			.ok_or_else(|| Error::parse("Row has no style!"))?;
 		let re = Regex::new(r"background: (#[0-9A-F]{6})").unwrap();
 		let color = re
-			.captures_iter(style)
-			.next()
-			.unwrap()
-			.get(1)
-			.unwrap()
+			.captures(style)
+			.and_then(|c| c.get(1))
+			.ok_or_else(|| Error::parse("Row has no background color!"))?
 			.as_str();
-		Profession::from_table_background_color(color).expect("Unknown profession!")
+		Profession::from_table_background_color(color)
+			.ok_or_else(|| Error::parse(format!("Unknown profession color {}!", color)))
 	}
 
-	pub fn determine_icon_url(col: ElementRef) -> String {
+	pub fn determine_icon_url(col: ElementRef) -> Result<String> {
 		let icon_selection = Selector::parse("a img").unwrap();
 		col.select(&icon_selection)
 			.next()
-			.unwrap()
-			.value()
-			.attr("src")
-			.unwrap()
-			.to_string()
+			.and_then(|img| img.value().attr("src"))
+			.map(|src| src.to_string())
+			.ok_or_else(|| Error::parse("Skill has no icon!"))
 	}
 
 	pub fn determine_resources<'a, I>(
 		cols: &mut I,
 		profession: Profession,
 		skill_name: &str,
-	) -> Vec<Resource>
+	) -> Result<Vec<Resource>>
 	where
 		I: iter::Iterator<Item = ElementRef<'a>>,
 	{
 		let mut res = vec![];
 
-		let specific_col = cols.next();
+		let specific_col = next_col(cols)?;
 		match profession {
 			Profession::Necromancer | Profession::Ritualist => {
-				if let Some(cost) = specific_col.map(sacrifice_value).unwrap() {
+				if let Some(cost) = sacrifice_value(specific_col)? {
 					res.push(Resource::Sacrifice(cost));
 				}
 			}
 			Profession::Warrior | Profession::Paragon | Profession::Dervish | Profession::Common => {
 				// Norn and Deldrimor Common skills have adrenaline costs
-				if let Some(cost) = specific_col.map(adrenaline_value).unwrap() {
+				if let Some(cost) = adrenaline_value(specific_col)? {
 					res.push(Resource::Adrenaline(cost));
 				}
 			}
 			Profession::Elementalist => {
 				if skill_name == "Over the Limit" {
-					if let Some(drain) = specific_col.map(upkeep_value).unwrap() {
+					if let Some(drain) = upkeep_value(specific_col)? {
 						res.push(Resource::Upkeep(drain));
 					}
-				} else {
-					if let Some(cost) = specific_col.map(overcast_value).unwrap() {
-						res.push(Resource::Overcast(cost));
-					}
+				} else if let Some(cost) = overcast_value(specific_col)? {
+					res.push(Resource::Overcast(cost));
 				}
 			}
 			Profession::Monk | Profession::Assassin => {
-				if let Some(drain) = specific_col.map(upkeep_value).unwrap() {
+				if let Some(drain) = upkeep_value(specific_col)? {
 					res.push(Resource::Upkeep(drain));
 				}
 			}
 			Profession::Mesmer | Profession::Ranger => (),
 		}
 
-		if let Some(cost) = cols.next().map(numerical_row_value).unwrap() {
+		if let Some(cost) = numerical_row_value(next_col(cols)?)? {
 			res.push(Resource::Energy(cost));
 		}
-		if let Some(time) = cols.next().map(cast_time_value).unwrap() {
+		if let Some(time) = cast_time_value(next_col(cols)?)? {
 			res.push(Resource::Cast(time));
 		}
-		if let Some(time) = cols.next().map(numerical_row_value).unwrap() {
+		if let Some(time) = numerical_row_value(next_col(cols)?)? {
 			res.push(Resource::Recharge(time));
 		}
 
-		res
+		Ok(res)
+	}
+
+	pub fn next_col<'a, I>(cols: &mut I) -> Result<ElementRef<'a>>
+	where
+		I: iter::Iterator<Item = ElementRef<'a>>,
+	{
+		cols.next().ok_or_else(|| Error::parse("Row ended early!"))
 	}
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Resource {
 	Recharge(u8),
 	Cast(f32),
@@ -140,23 +146,46 @@ impl Resource {
 	}
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Skill {
+	/// The in-game ID, which stays the same when a skill gets renamed.
+	/// Every variant of a skill split by game mode or allegiance has its own.
+	#[serde(default)]
+	pub id: Option<u16>,
 	pub icon_url: String,
 	pub name: String,
 	pub profession: Profession,
-	pub attribute: Option<String>,
-	skill_type: String,
+	pub attribute: Option<Attribute>,
+	skill_type: SkillType,
 	pub description: String,
 	pub resources: Vec<Resource>,
 	is_quest_reward: bool,
-	campaign: String,
+	campaign: Campaign,
 	pub split_by_game_mode: Option<GameMode>,
 	is_pve_only: bool,
 	pub is_elite: bool,
+	/// Only there if we scraped the skill's own wiki page, too.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub details: Option<SkillDetails>,
 }
 
 impl Skill {
+	pub fn skill_type(&self) -> SkillType {
+		self.skill_type
+	}
+
+	pub fn is_quest_reward(&self) -> bool {
+		self.is_quest_reward
+	}
+
+	pub fn campaign(&self) -> Campaign {
+		self.campaign
+	}
+
+	pub fn is_pve_only(&self) -> bool {
+		self.is_pve_only
+	}
+
 	pub fn type_line(&self) -> String {
 		let mut line = string_builder::Builder::default();
 		if self.is_elite {
@@ -169,14 +198,10 @@ impl Skill {
 		if self.is_pve_only {
 			line.append("PvE ");
 		}
-		line.append(&*self.skill_type);
-		if let Some(attribute) = &self.attribute {
+		line.append(self.skill_type.name());
+		if let Some(attribute) = self.attribute {
 			line.append(" (");
-			if attribute.ends_with(" rank") {
-				line.append(attribute.trim_end_matches(" rank"));
-			} else {
-				line.append(&**attribute);
-			}
+			line.append(attribute.short_name());
 			line.append(")")
 		}
 
@@ -184,70 +209,262 @@ impl Skill {
 	}
 
 	pub fn is_pvp_variant(&self) -> bool {
-		match self.split_by_game_mode {
-			Some(GameMode::PvP) => true,
-			Some(GameMode::Codex) => true,
-			_ => false,
-		}
+		matches!(
+			self.split_by_game_mode,
+			Some(GameMode::PvP) | Some(GameMode::Codex)
+		)
 	}
 
-	pub fn icon_path(&self) -> String {
-		// I don't think we need to treat PvE/PvP split skills any differently here.
-		let allegiance = match &self.attribute {
-			Some(k) if k.starts_with("Kurzick") => "-Kurzick",
-			Some(l) if l.starts_with("Luxon") => "-Luxon",
+	/// The description with its `low...high` ranges filled in for `rank` in the skill's attribute.
+	/// Ranges we have no progression for are left as they are.
+	pub fn description_at(&self, rank: u8) -> String {
+		let details = match &self.details {
+			Some(details) => details,
+			None => return self.description.clone(),
+		};
+		let range = Regex::new(r"(\d+(?:\.\d+)?)\.\.\.(\d+(?:\.\d+)?)").unwrap();
+		range
+			.replace_all(&self.description, |caps: &Captures| {
+				let value = match (caps[1].parse(), caps[2].parse()) {
+					(Ok(low), Ok(high)) => details
+						.progression_for(low, high)
+						.and_then(|row| row.at(rank)),
+					_ => None,
+				};
+				value.map_or_else(|| caps[0].to_owned(), |value| value.to_string())
+			})
+			.into_owned()
+	}
+
+	/// The name the game uses to tell this variant of a skill apart from the others.
+	pub fn variant_name(&self) -> String {
+		let variant = match (&self.split_by_game_mode, self.attribute) {
+			(Some(GameMode::PvP), _) => " (PvP)",
+			(Some(GameMode::Codex), _) => " (Codex)",
+			(_, Some(Attribute::KurzickRank)) => " (Kurzick)",
+			(_, Some(Attribute::LuxonRank)) => " (Luxon)",
 			_ => "",
 		};
-		let naive_path = format!("cache/images/{}{}.jpg", self.name, allegiance);
-		helpers::sanitize_file_name(naive_path)
+		format!("{}{}", self.name, variant)
 	}
 
-	pub fn card_path(&self) -> String {
+	/// Whether both are versions of the same skill, even if it got renamed in between.
+	pub fn is_same_skill(&self, other: &Skill) -> bool {
+		match (self.id, other.id) {
+			(Some(id), Some(other_id)) => id == other_id,
+			_ => self.variant_name() == other.variant_name(),
+		}
+	}
+
+	/// The name shared by this skill's cached icon and its rendered card, without an extension.
+	pub fn file_name(&self) -> String {
+		if let Some(id) = self.id {
+			return id.to_string();
+		}
 		// I don't think we need to treat PvE/PvP split skills any differently here.
-		let allegiance = match &self.attribute {
-			Some(k) if k.starts_with("Kurzick") => "-Kurzick",
-			Some(l) if l.starts_with("Luxon") => "-Luxon",
+		let allegiance = match self.attribute {
+			Some(Attribute::KurzickRank) => "-Kurzick",
+			Some(Attribute::LuxonRank) => "-Luxon",
 			_ => "",
 		};
-		let naive_path = format!("cards/{}{}.png", self.name, allegiance);
-		helpers::sanitize_file_name(naive_path)
+		helpers::sanitize_file_name(format!("{}{}", self.name, allegiance))
 	}
 
 	pub fn hidden() -> Self {
 		Self {
+			id: None,
 			name: "Unidentified Skill".to_owned(),
 			icon_url: "/images/7/7b/Blind.jpg".to_owned(),
 			profession: Profession::Common,
-			attribute: Some("???".to_owned()),
-			skill_type: "???".to_owned(),
+			attribute: Some(Attribute::Unidentified),
+			skill_type: SkillType::Unidentified,
 			description: "??? ??...??...?? ????, ??????.".to_owned(),
 			is_elite: false,
 			is_pve_only: false,
 			is_quest_reward: false,
 			resources: vec![],
-			campaign: "???".to_owned(),
+			campaign: Campaign::Core,
 			split_by_game_mode: None,
+			details: None,
 		}
 	}
 }
 
+/// Writes a column that holds a list as JSON text, so it fits in a single cell.
+mod json_column {
+	use serde::de::{DeserializeOwned, Error as _};
+	use serde::ser::Error as _;
+	use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+	pub fn serialize<T: Serialize, S: Serializer>(
+		value: &Option<T>,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		match value {
+			Some(value) => {
+				serializer.serialize_some(&serde_json::to_string(value).map_err(S::Error::custom)?)
+			}
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub fn deserialize<'de, T: DeserializeOwned, D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<Option<T>, D::Error> {
+		Option::<String>::deserialize(deserializer)?
+			.filter(|json| !json.is_empty())
+			.map(|json| serde_json::from_str(&json).map_err(D::Error::custom))
+			.transpose()
+	}
+}
+
+/// A skill flattened into a single row, for reading it from a spreadsheet or exporting it to one.
+/// Every resource gets a column of its own, left empty if the skill doesn't cost it.
+/// Details from the skill's own wiki page go into a column each as JSON, left empty if we
+/// didn't scrape the page. Their ID is the skill's own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkillRecord {
+	#[serde(default)]
+	pub id: Option<u16>,
+	pub name: String,
+	pub profession: Profession,
+	pub skill_type: SkillType,
+	pub attribute: Option<Attribute>,
+	pub campaign: Campaign,
+	pub description: String,
+	pub is_elite: bool,
+	pub is_quest_reward: bool,
+	pub is_pve_only: bool,
+	pub split_by_game_mode: Option<GameMode>,
+	pub icon_url: String,
+	pub adrenaline: Option<u8>,
+	pub overcast: Option<u8>,
+	pub upkeep: Option<i8>,
+	pub sacrifice: Option<u8>,
+	pub energy: Option<u8>,
+	pub cast: Option<f32>,
+	pub recharge: Option<u8>,
+	#[serde(default, with = "json_column")]
+	pub progression: Option<Vec<Progression>>,
+	#[serde(default, with = "json_column")]
+	pub related_skills: Option<Vec<String>>,
+	#[serde(default, with = "json_column")]
+	pub notes: Option<Vec<String>>,
+	#[serde(default, with = "json_column")]
+	pub acquisition: Option<Vec<String>>,
+}
+
+impl From<SkillRecord> for Skill {
+	fn from(record: SkillRecord) -> Self {
+		// same order as on the wiki, profession specific costs first
+		let resources = [
+			record.adrenaline.map(Resource::Adrenaline),
+			record.overcast.map(Resource::Overcast),
+			record.upkeep.map(Resource::Upkeep),
+			record.sacrifice.map(Resource::Sacrifice),
+			record.energy.map(Resource::Energy),
+			record.cast.map(Resource::Cast),
+			record.recharge.map(Resource::Recharge),
+		];
+		let has_details = record.progression.is_some()
+			|| record.related_skills.is_some()
+			|| record.notes.is_some()
+			|| record.acquisition.is_some();
+		let details = if has_details {
+			Some(SkillDetails {
+				id: record.id,
+				progression: record.progression.unwrap_or_default(),
+				related_skills: record.related_skills.unwrap_or_default(),
+				notes: record.notes.unwrap_or_default(),
+				acquisition: record.acquisition.unwrap_or_default(),
+			})
+		} else {
+			None
+		};
+		Self {
+			id: record.id,
+			icon_url: record.icon_url,
+			name: record.name,
+			profession: record.profession,
+			attribute: record.attribute,
+			skill_type: record.skill_type,
+			description: record.description,
+			resources: resources.iter().flatten().copied().collect(),
+			is_quest_reward: record.is_quest_reward,
+			campaign: record.campaign,
+			split_by_game_mode: record.split_by_game_mode,
+			is_pve_only: record.is_pve_only,
+			is_elite: record.is_elite,
+			details,
+		}
+	}
+}
+
+impl From<&Skill> for SkillRecord {
+	fn from(skill: &Skill) -> Self {
+		let mut record = Self {
+			id: skill.id,
+			name: skill.name.clone(),
+			profession: skill.profession,
+			skill_type: skill.skill_type,
+			attribute: skill.attribute,
+			campaign: skill.campaign,
+			description: skill.description.clone(),
+			is_elite: skill.is_elite,
+			is_quest_reward: skill.is_quest_reward,
+			is_pve_only: skill.is_pve_only,
+			split_by_game_mode: skill.split_by_game_mode,
+			icon_url: skill.icon_url.clone(),
+			adrenaline: None,
+			overcast: None,
+			upkeep: None,
+			sacrifice: None,
+			energy: None,
+			cast: None,
+			recharge: None,
+			progression: None,
+			related_skills: None,
+			notes: None,
+			acquisition: None,
+		};
+		for resource in &skill.resources {
+			match *resource {
+				Resource::Adrenaline(value) => record.adrenaline = Some(value),
+				Resource::Overcast(value) => record.overcast = Some(value),
+				Resource::Upkeep(value) => record.upkeep = Some(value),
+				Resource::Sacrifice(value) => record.sacrifice = Some(value),
+				Resource::Energy(value) => record.energy = Some(value),
+				Resource::Cast(value) => record.cast = Some(value),
+				Resource::Recharge(value) => record.recharge = Some(value),
+			}
+		}
+		if let Some(details) = &skill.details {
+			record.progression = Some(details.progression.clone());
+			record.related_skills = Some(details.related_skills.clone());
+			record.notes = Some(details.notes.clone());
+			record.acquisition = Some(details.acquisition.clone());
+		}
+		record
+	}
+}
+
 impl TryFrom<ElementRef<'_>> for Skill {
-	type Error = &'static str;
+	type Error = Error;
 
-	fn try_from(row: ElementRef) -> Result<Self, Self::Error> {
+	fn try_from(row: ElementRef) -> Result<Self> {
 		if row.value().name() != "tr" {
-			return Err("Not a table row!");
+			return Err(Error::parse("Not a table row!"));
 		}
 		let select_cols = Selector::parse("th, td").unwrap();
 		if row.select(&select_cols).count() != 10 {
-			return Err("Wrong number of cols!");
+			return Err(Error::parse("Wrong number of cols!"));
 		}
 		let mut cols = row.select(&select_cols);
 
-		let profession = helpers::determine_profession(row);
-		let icon_url = helpers::determine_icon_url(cols.next().unwrap());
+		let profession = helpers::determine_profession(row)?;
+		let icon_url = helpers::determine_icon_url(helpers::next_col(&mut cols)?)?;
 
-		let mut name: String = cols.next().map(innerText).unwrap();
+		let mut name: String = innerText(helpers::next_col(&mut cols)?);
 		let mut split_by_game_mode = None;
 		if name.ends_with(" (PvP)") {
 			split_by_game_mode = Some(GameMode::PvP);
@@ -258,24 +475,33 @@ impl TryFrom<ElementRef<'_>> for Skill {
 			name = name.trim_end_matches(" (Codex)").to_owned();
 		}
 
-		let full_description: String = cols.next().map(innerText).unwrap();
+		let full_description: String = innerText(helpers::next_col(&mut cols)?);
 		let full_description = full_description.replace(" [sic]", "");
 		let mut split_description = full_description.splitn(2, ". ");
-		let mut skill_type = split_description.next().unwrap().to_string();
+		let mut skill_type = split_description.next().unwrap();
 		let mut is_elite = false;
 		if skill_type.starts_with("Elite ") {
 			is_elite = true;
-			skill_type = skill_type.trim_start_matches("Elite ").to_owned();
+			skill_type = skill_type.trim_start_matches("Elite ");
 		}
-		let description = split_description.next().unwrap().to_string();
+		let skill_type: SkillType = skill_type.parse().map_err(Error::Parse)?;
+		let description = split_description
+			.next()
+			.ok_or_else(|| Error::parse("Description has no skill type!"))?
+			.to_string();
 
-		let resources = helpers::determine_resources(&mut cols.by_ref().take(4), profession, &*name);
+		let resources =
+			helpers::determine_resources(&mut cols.by_ref().take(4), profession, &name)?;
 
-		let is_quest_reward = !cols.next().unwrap().inner_html().is_empty();
-		let attribute: Option<String> = cols.next().map(attribute_value).unwrap();
-		let campaign: String = cols.next().map(innerText).unwrap();
+		let is_quest_reward = !helpers::next_col(&mut cols)?.inner_html().is_empty();
+		let attribute: Option<Attribute> = attribute_value(helpers::next_col(&mut cols)?)?
+			.map(|attribute| attribute.parse().map_err(Error::Parse))
+			.transpose()?;
+		let campaign: Campaign = innerText(helpers::next_col(&mut cols)?)
+			.parse()
+			.map_err(Error::Parse)?;
 
-		let is_pve_only = (attribute.is_some() && attribute.clone().unwrap().ends_with(" rank"))
+		let is_pve_only = matches!(attribute, Some(a) if a.is_rank())
 			|| [
 				"Signet of Capture",
 				"\"Together as One!\"",
@@ -292,6 +518,7 @@ impl TryFrom<ElementRef<'_>> for Skill {
 			.contains(&&*name);
 
 		Ok(Self {
+			id: None,
 			name,
 			description,
 			skill_type,
@@ -304,17 +531,18 @@ impl TryFrom<ElementRef<'_>> for Skill {
 			is_elite,
 			split_by_game_mode,
 			is_pve_only,
+			details: None,
 		})
 	}
 }
 
-fn attribute_value(el: ElementRef) -> Option<String> {
-	let node = el.children().next().unwrap().value();
-	if node.is_text() {
-		Some(node.as_text().unwrap().trim().to_string())
-	} else {
-		None
-	}
+fn attribute_value(el: ElementRef) -> Result<Option<String>> {
+	let node = el
+		.children()
+		.next()
+		.ok_or_else(|| Error::parse("Empty cell!"))?
+		.value();
+	Ok(node.as_text().map(|text| text.trim().to_string()))
 }
 
 #[allow(non_snake_case)] // This mimics the element.innerText method found in browsers.
@@ -326,87 +554,74 @@ fn innerText(el: ElementRef) -> String {
 		.to_string()
 }
 
-fn numerical_row_value(el: ElementRef) -> Option<u8> {
+fn parse_number<T: str::FromStr>(text: &str) -> Result<T> {
+	text
+		.trim()
+		.parse::<T>()
+		.map_err(|_| Error::parse(format!("{:?} is not a valid number!", text)))
+}
+
+fn span_text(el: ElementRef) -> Result<String> {
 	let select_span = Selector::parse("span").unwrap();
-	let text: String = el.select(&select_span).next().map(innerText).unwrap();
+	el.select(&select_span)
+		.next()
+		.map(innerText)
+		.ok_or_else(|| Error::parse("Cell has no value!"))
+}
+
+fn numerical_row_value(el: ElementRef) -> Result<Option<u8>> {
+	let text = span_text(el)?;
 	if text == "morale boost" {
-		return None;
+		return Ok(None);
 	}
-	match text.parse::<u8>().unwrap() {
-		n if n == 0 => None,
-		n => Some(n),
+	match parse_number::<u8>(&text)? {
+		0 => Ok(None),
+		n => Ok(Some(n)),
 	}
 }
 
-fn parse_described_float(capture: Captures) -> f32 {
-	let dividend = capture // upper
-		.get(1)
-		.unwrap()
-		.as_str()
-		.trim()
-		.parse::<f32>()
-		.unwrap();
-	let divisor = capture // lower
-		.get(2)
-		.unwrap()
-		.as_str()
-		.trim()
-		.parse::<f32>()
-		.unwrap();
-	dividend / divisor
+fn parse_described_float(capture: Captures) -> Result<f32> {
+	let dividend = parse_number::<f32>(&capture[1])?; // upper
+	let divisor = parse_number::<f32>(&capture[2])?; // lower
+	Ok(dividend / divisor)
 }
 
-fn cast_time_value(el: ElementRef) -> Option<f32> {
-	let select_span = Selector::parse("span").unwrap();
-	let text: String = el.select(&select_span).next().map(innerText).unwrap();
+fn cast_time_value(el: ElementRef) -> Result<Option<f32>> {
+	let text = span_text(el)?;
 	let division_pattern = Regex::new("([0-9]+)/([0-9]+)").unwrap();
-	let cast_time = if division_pattern.is_match(&*text) {
-		parse_described_float(division_pattern.captures_iter(&text).next().unwrap())
-	} else {
-		text.parse::<f32>().unwrap()
+	let cast_time = match division_pattern.captures(&text) {
+		Some(capture) => parse_described_float(capture)?,
+		None => parse_number::<f32>(&text)?,
 	};
 
 	match cast_time {
-		n if !n.is_normal() => None,
-		n => Some(n),
+		n if !n.is_normal() => Ok(None),
+		n => Ok(Some(n)),
 	}
 }
 
-fn upkeep_value(el: ElementRef) -> Option<i8> {
-	match attribute_value(el) {
-		Some(val) => Some(val.parse::<i8>().unwrap()),
-		None => None,
-	}
+fn upkeep_value(el: ElementRef) -> Result<Option<i8>> {
+	attribute_value(el)?
+		.map(|val| parse_number::<i8>(&val))
+		.transpose()
 }
 
-fn adrenaline_value(el: ElementRef) -> Option<u8> {
-	match upkeep_value(el) {
-		Some(n) if n < 0 => panic!("Unexpected negative value {}", n),
-		Some(n) => Some(n as u8),
-		None => None,
+fn adrenaline_value(el: ElementRef) -> Result<Option<u8>> {
+	match upkeep_value(el)? {
+		Some(n) if n < 0 => Err(Error::parse(format!("Unexpected negative value {}", n))),
+		Some(n) => Ok(Some(n as u8)),
+		None => Ok(None),
 	}
 }
 
-fn overcast_value(el: ElementRef) -> Option<u8> {
+fn overcast_value(el: ElementRef) -> Result<Option<u8>> {
 	adrenaline_value(el)
 }
 
-fn sacrifice_value(el: ElementRef) -> Option<u8> {
-	let node = el.children().skip(1).next()?.value();
-	if node.is_text() {
-		Some(
-			node
-				.as_text()
-				.unwrap()
-				.to_string()
-				.split('%')
-				.next()
-				.unwrap()
-				.parse::<u8>()
-				.unwrap(),
-		)
-	} else {
-		None
+fn sacrifice_value(el: ElementRef) -> Result<Option<u8>> {
+	match el.children().nth(1).map(|node| node.value().as_text()) {
+		Some(Some(text)) => Ok(Some(parse_number(text.split('%').next().unwrap())?)),
+		_ => Ok(None),
 	}
 }
 
@@ -416,8 +631,444 @@ pub enum GameMode {
 	PvP,
 	Codex,
 }
+impl fmt::Display for GameMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{:?}", self)
+	}
+}
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+/// What kind of skill something is, like `Hex Spell` or `Sword Attack`, without the "Elite".
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub enum SkillType {
+	Attack,
+	AxeAttack,
+	BowAttack,
+	DaggerAttack,
+	LeadAttack,
+	OffHandAttack,
+	DualAttack,
+	HammerAttack,
+	MeleeAttack,
+	PetAttack,
+	ScytheAttack,
+	SpearAttack,
+	SwordAttack,
+	Spell,
+	HexSpell,
+	EnchantmentSpell,
+	FlashEnchantmentSpell,
+	ItemSpell,
+	WardSpell,
+	WeaponSpell,
+	WellSpell,
+	Glyph,
+	BindingRitual,
+	NatureRitual,
+	EbonVanguardRitual,
+	Skill,
+	Stance,
+	Shout,
+	Chant,
+	Echo,
+	Signet,
+	Preparation,
+	Trap,
+	Form,
+	/// Only on the face-down placeholder card, see [`Skill::hidden`]. Never parsed.
+	Unidentified,
+}
+impl SkillType {
+	/// Every skill type we can parse.
+	const ALL: [Self; 34] = [
+		Self::Attack,
+		Self::AxeAttack,
+		Self::BowAttack,
+		Self::DaggerAttack,
+		Self::LeadAttack,
+		Self::OffHandAttack,
+		Self::DualAttack,
+		Self::HammerAttack,
+		Self::MeleeAttack,
+		Self::PetAttack,
+		Self::ScytheAttack,
+		Self::SpearAttack,
+		Self::SwordAttack,
+		Self::Spell,
+		Self::HexSpell,
+		Self::EnchantmentSpell,
+		Self::FlashEnchantmentSpell,
+		Self::ItemSpell,
+		Self::WardSpell,
+		Self::WeaponSpell,
+		Self::WellSpell,
+		Self::Glyph,
+		Self::BindingRitual,
+		Self::NatureRitual,
+		Self::EbonVanguardRitual,
+		Self::Skill,
+		Self::Stance,
+		Self::Shout,
+		Self::Chant,
+		Self::Echo,
+		Self::Signet,
+		Self::Preparation,
+		Self::Trap,
+		Self::Form,
+	];
+
+	pub fn iter() -> impl Iterator<Item = Self> {
+		Self::ALL.iter().copied()
+	}
+
+	/// The name the wiki uses.
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Attack => "Attack",
+			Self::AxeAttack => "Axe Attack",
+			Self::BowAttack => "Bow Attack",
+			Self::DaggerAttack => "Dagger Attack",
+			Self::LeadAttack => "Lead Attack",
+			Self::OffHandAttack => "Off-Hand Attack",
+			Self::DualAttack => "Dual Attack",
+			Self::HammerAttack => "Hammer Attack",
+			Self::MeleeAttack => "Melee Attack",
+			Self::PetAttack => "Pet Attack",
+			Self::ScytheAttack => "Scythe Attack",
+			Self::SpearAttack => "Spear Attack",
+			Self::SwordAttack => "Sword Attack",
+			Self::Spell => "Spell",
+			Self::HexSpell => "Hex Spell",
+			Self::EnchantmentSpell => "Enchantment Spell",
+			Self::FlashEnchantmentSpell => "Flash Enchantment Spell",
+			Self::ItemSpell => "Item Spell",
+			Self::WardSpell => "Ward Spell",
+			Self::WeaponSpell => "Weapon Spell",
+			Self::WellSpell => "Well Spell",
+			Self::Glyph => "Glyph",
+			Self::BindingRitual => "Binding Ritual",
+			Self::NatureRitual => "Nature Ritual",
+			Self::EbonVanguardRitual => "Ebon Vanguard Ritual",
+			Self::Skill => "Skill",
+			Self::Stance => "Stance",
+			Self::Shout => "Shout",
+			Self::Chant => "Chant",
+			Self::Echo => "Echo",
+			Self::Signet => "Signet",
+			Self::Preparation => "Preparation",
+			Self::Trap => "Trap",
+			Self::Form => "Form",
+			Self::Unidentified => "???",
+		}
+	}
+
+	pub fn is_attack(self) -> bool {
+		self.name().ends_with("Attack")
+	}
+
+	pub fn is_spell(self) -> bool {
+		self.name().ends_with("Spell")
+	}
+}
+impl str::FromStr for SkillType {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		Self::iter()
+			.find(|t| t.name().eq_ignore_ascii_case(s))
+			.ok_or_else(|| format!("Unknown skill type {}", s))
+	}
+}
+impl TryFrom<String> for SkillType {
+	type Error = String;
+
+	fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+		s.parse()
+	}
+}
+impl From<SkillType> for String {
+	fn from(skill_type: SkillType) -> Self {
+		skill_type.name().to_owned()
+	}
+}
+impl fmt::Display for SkillType {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.name())
+	}
+}
+
+/// The attribute a skill scales with. PvE skills scale with a title track rank instead.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub enum Attribute {
+	FastCasting,
+	IllusionMagic,
+	DominationMagic,
+	InspirationMagic,
+	BloodMagic,
+	DeathMagic,
+	SoulReaping,
+	Curses,
+	AirMagic,
+	EarthMagic,
+	FireMagic,
+	WaterMagic,
+	EnergyStorage,
+	HealingPrayers,
+	SmitingPrayers,
+	ProtectionPrayers,
+	DivineFavor,
+	Strength,
+	AxeMastery,
+	HammerMastery,
+	Swordsmanship,
+	Tactics,
+	BeastMastery,
+	Expertise,
+	WildernessSurvival,
+	Marksmanship,
+	DaggerMastery,
+	DeadlyArts,
+	ShadowArts,
+	Communing,
+	RestorationMagic,
+	ChannelingMagic,
+	CriticalStrikes,
+	SpawningPower,
+	SpearMastery,
+	Command,
+	Motivation,
+	Leadership,
+	ScytheMastery,
+	WindPrayers,
+	EarthPrayers,
+	Mysticism,
+	/// What the wiki lists for skills that come in a Kurzick and a Luxon variant.
+	/// [`touch_up_skills`](crate::io::touch_up_skills) turns it into one of those two.
+	AllegianceRank,
+	KurzickRank,
+	LuxonRank,
+	SunspearRank,
+	LightbringerRank,
+	AsuraRank,
+	DeldrimorRank,
+	EbonVanguardRank,
+	NornRank,
+	/// Only on the face-down placeholder card, see [`Skill::hidden`]. Never parsed.
+	Unidentified,
+}
+impl Attribute {
+	/// Every attribute we can parse.
+	const ALL: [Self; 51] = [
+		Self::FastCasting,
+		Self::IllusionMagic,
+		Self::DominationMagic,
+		Self::InspirationMagic,
+		Self::BloodMagic,
+		Self::DeathMagic,
+		Self::SoulReaping,
+		Self::Curses,
+		Self::AirMagic,
+		Self::EarthMagic,
+		Self::FireMagic,
+		Self::WaterMagic,
+		Self::EnergyStorage,
+		Self::HealingPrayers,
+		Self::SmitingPrayers,
+		Self::ProtectionPrayers,
+		Self::DivineFavor,
+		Self::Strength,
+		Self::AxeMastery,
+		Self::HammerMastery,
+		Self::Swordsmanship,
+		Self::Tactics,
+		Self::BeastMastery,
+		Self::Expertise,
+		Self::WildernessSurvival,
+		Self::Marksmanship,
+		Self::DaggerMastery,
+		Self::DeadlyArts,
+		Self::ShadowArts,
+		Self::Communing,
+		Self::RestorationMagic,
+		Self::ChannelingMagic,
+		Self::CriticalStrikes,
+		Self::SpawningPower,
+		Self::SpearMastery,
+		Self::Command,
+		Self::Motivation,
+		Self::Leadership,
+		Self::ScytheMastery,
+		Self::WindPrayers,
+		Self::EarthPrayers,
+		Self::Mysticism,
+		Self::AllegianceRank,
+		Self::KurzickRank,
+		Self::LuxonRank,
+		Self::SunspearRank,
+		Self::LightbringerRank,
+		Self::AsuraRank,
+		Self::DeldrimorRank,
+		Self::EbonVanguardRank,
+		Self::NornRank,
+	];
+
+	pub fn iter() -> impl Iterator<Item = Self> {
+		Self::ALL.iter().copied()
+	}
+
+	/// The name the wiki uses.
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::FastCasting => "Fast Casting",
+			Self::IllusionMagic => "Illusion Magic",
+			Self::DominationMagic => "Domination Magic",
+			Self::InspirationMagic => "Inspiration Magic",
+			Self::BloodMagic => "Blood Magic",
+			Self::DeathMagic => "Death Magic",
+			Self::SoulReaping => "Soul Reaping",
+			Self::Curses => "Curses",
+			Self::AirMagic => "Air Magic",
+			Self::EarthMagic => "Earth Magic",
+			Self::FireMagic => "Fire Magic",
+			Self::WaterMagic => "Water Magic",
+			Self::EnergyStorage => "Energy Storage",
+			Self::HealingPrayers => "Healing Prayers",
+			Self::SmitingPrayers => "Smiting Prayers",
+			Self::ProtectionPrayers => "Protection Prayers",
+			Self::DivineFavor => "Divine Favor",
+			Self::Strength => "Strength",
+			Self::AxeMastery => "Axe Mastery",
+			Self::HammerMastery => "Hammer Mastery",
+			Self::Swordsmanship => "Swordsmanship",
+			Self::Tactics => "Tactics",
+			Self::BeastMastery => "Beast Mastery",
+			Self::Expertise => "Expertise",
+			Self::WildernessSurvival => "Wilderness Survival",
+			Self::Marksmanship => "Marksmanship",
+			Self::DaggerMastery => "Dagger Mastery",
+			Self::DeadlyArts => "Deadly Arts",
+			Self::ShadowArts => "Shadow Arts",
+			Self::Communing => "Communing",
+			Self::RestorationMagic => "Restoration Magic",
+			Self::ChannelingMagic => "Channeling Magic",
+			Self::CriticalStrikes => "Critical Strikes",
+			Self::SpawningPower => "Spawning Power",
+			Self::SpearMastery => "Spear Mastery",
+			Self::Command => "Command",
+			Self::Motivation => "Motivation",
+			Self::Leadership => "Leadership",
+			Self::ScytheMastery => "Scythe Mastery",
+			Self::WindPrayers => "Wind Prayers",
+			Self::EarthPrayers => "Earth Prayers",
+			Self::Mysticism => "Mysticism",
+			Self::AllegianceRank => "Allegiance rank",
+			Self::KurzickRank => "Kurzick rank",
+			Self::LuxonRank => "Luxon rank",
+			Self::SunspearRank => "Sunspear rank",
+			Self::LightbringerRank => "Lightbringer rank",
+			Self::AsuraRank => "Asura rank",
+			Self::DeldrimorRank => "Deldrimor rank",
+			Self::EbonVanguardRank => "Ebon Vanguard rank",
+			Self::NornRank => "Norn rank",
+			Self::Unidentified => "???",
+		}
+	}
+
+	/// Whether this is a title track rank, which only PvE skills scale with.
+	pub fn is_rank(self) -> bool {
+		self.name().ends_with(" rank")
+	}
+
+	/// The name without " rank", like it's printed on the cards.
+	pub fn short_name(self) -> &'static str {
+		self.name().trim_end_matches(" rank")
+	}
+}
+impl str::FromStr for Attribute {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		Self::iter()
+			.find(|a| a.name().eq_ignore_ascii_case(s))
+			.ok_or_else(|| format!("Unknown attribute {}", s))
+	}
+}
+impl TryFrom<String> for Attribute {
+	type Error = String;
+
+	fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+		s.parse()
+	}
+}
+impl From<Attribute> for String {
+	fn from(attribute: Attribute) -> Self {
+		attribute.name().to_owned()
+	}
+}
+impl fmt::Display for Attribute {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.name())
+	}
+}
+
+/// The game a skill was first added in. Core skills come with every campaign.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum Campaign {
+	Core,
+	Prophecies,
+	Factions,
+	Nightfall,
+	#[serde(rename = "Eye of the North")]
+	EyeOfTheNorth,
+}
+impl Campaign {
+	pub fn iter() -> impl Iterator<Item = Self> {
+		[
+			Self::Core,
+			Self::Prophecies,
+			Self::Factions,
+			Self::Nightfall,
+			Self::EyeOfTheNorth,
+		]
+		.iter()
+		.copied()
+	}
+
+	/// The letter we mark cards of this campaign with. Core skills don't get one.
+	pub fn glyph(self) -> Option<&'static str> {
+		match self {
+			Self::Core => None,
+			Self::Prophecies => Some("P"),
+			Self::Factions => Some("F"),
+			Self::Nightfall => Some("N"),
+			Self::EyeOfTheNorth => Some("E"),
+		}
+	}
+}
+impl str::FromStr for Campaign {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		Self::iter()
+			.find(|c| c.to_string().eq_ignore_ascii_case(s))
+			.ok_or_else(|| format!("Unknown campaign {}", s))
+	}
+}
+impl fmt::Display for Campaign {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let name = match self {
+			Self::Core => "Core",
+			Self::Prophecies => "Prophecies",
+			Self::Factions => "Factions",
+			Self::Nightfall => "Nightfall",
+			Self::EyeOfTheNorth => "Eye of the North",
+		};
+		write!(f, "{}", name)
+	}
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
 pub enum Profession {
 	Warrior,
 	Ranger,
@@ -467,6 +1118,15 @@ impl Profession {
 		.copied()
 	}
 }
+impl str::FromStr for Profession {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		Self::iter()
+			.find(|p| p.to_string().eq_ignore_ascii_case(s))
+			.ok_or_else(|| format!("Unknown profession {}", s))
+	}
+}
 impl fmt::Display for Profession {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		let name = match self {
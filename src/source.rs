@@ -0,0 +1,262 @@
+use crate::details::{parse_details, SkillDetails};
+use crate::download::Downloader;
+use crate::error::{Error, Report, Result};
+use crate::io::{self, CacheMeta};
+use crate::skill::{GameMode, Profession, Skill, SkillRecord};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Where the official wiki lives.
+pub const WIKI_URL: &str = "https://wiki.guildwars.com";
+/// The wiki page listing the ID of every skill.
+pub const SKILL_ID_PAGE: &str = "Skill_template_format/Skill_list";
+
+/// Somewhere we can get skills from.
+pub trait SkillSource {
+	/// Gets the skills of `profession`, recording any we can't make sense of in `report`.
+	///
+	/// `cached` describes the skills we got last time, if any.
+	/// Sources that can tell return `None` if nothing changed since.
+	fn fetch(
+		&self,
+		profession: Profession,
+		cached: Option<&CacheMeta>,
+		report: &mut Report,
+	) -> Result<Option<Fetched>>;
+}
+
+impl<T: SkillSource + ?Sized> SkillSource for &T {
+	fn fetch(
+		&self,
+		profession: Profession,
+		cached: Option<&CacheMeta>,
+		report: &mut Report,
+	) -> Result<Option<Fetched>> {
+		(**self).fetch(profession, cached, report)
+	}
+}
+
+/// Skills we just got from a source, and where they came from.
+#[derive(Debug)]
+pub struct Fetched {
+	pub skills: Vec<Skill>,
+	pub meta: CacheMeta,
+}
+
+/// The name of the wiki page listing the skills of `profession`.
+pub fn skill_list_page(profession: Profession) -> String {
+	format!("List_of_{}_skills", profession.to_string().to_lowercase())
+}
+
+/// The name of a skill's own page on the wiki.
+pub fn skill_page(skill: &Skill) -> String {
+	let title = match skill.split_by_game_mode {
+		Some(GameMode::PvP) => format!("{} (PvP)", skill.name),
+		Some(GameMode::Codex) => format!("{} (Codex)", skill.name),
+		_ => skill.name.clone(),
+	};
+	title.replace(' ', "_")
+}
+
+fn parse_page(raw_html: &[u8], meta: CacheMeta, report: &mut Report) -> Fetched {
+	let raw_html = String::from_utf8_lossy(raw_html);
+	Fetched {
+		skills: io::touch_up_skills(io::parse_skills(&raw_html, report)),
+		meta,
+	}
+}
+
+/// The live wiki, or anything that serves the same pages, like a local stand-in.
+pub struct Wiki {
+	base_url: String,
+	downloader: Downloader,
+	/// We only need to get these once, no matter how many professions we fetch.
+	skill_ids: Mutex<Option<HashMap<String, u16>>>,
+}
+
+impl Wiki {
+	pub fn new(base_url: impl Into<String>, downloader: Downloader) -> Self {
+		Self {
+			base_url: base_url.into().trim_end_matches('/').to_owned(),
+			downloader,
+			skill_ids: Mutex::new(None),
+		}
+	}
+
+	/// Turns a path on the wiki, like a skill's `icon_url`, into a full URL.
+	pub fn url(&self, path: &str) -> String {
+		format!("{}{}", self.base_url, path)
+	}
+
+	pub fn downloader(&self) -> &Downloader {
+		&self.downloader
+	}
+
+	/// Cards only need skill IDs for their file names and order, so if we can't get them,
+	/// we record that in `report` once and carry on without.
+	fn skill_ids(&self, report: &mut Report) -> HashMap<String, u16> {
+		let mut skill_ids = self.skill_ids.lock().unwrap();
+		skill_ids
+			.get_or_insert_with(|| {
+				match self
+					.downloader
+					.get(&self.url(&format!("/wiki/{}", SKILL_ID_PAGE)))
+				{
+					Ok(page) => io::parse_skill_ids(&String::from_utf8_lossy(&page.body)),
+					Err(err) => {
+						report.record(SKILL_ID_PAGE, err);
+						HashMap::new()
+					}
+				}
+			})
+			.clone()
+	}
+
+	/// Scrapes a skill's own page for what the skill lists leave out.
+	pub fn skill_details(&self, skill: &Skill) -> Result<SkillDetails> {
+		let page = self
+			.downloader
+			.get(&self.url(&format!("/wiki/{}", skill_page(skill))))?;
+		parse_details(&String::from_utf8_lossy(&page.body))
+	}
+}
+
+impl SkillSource for Wiki {
+	fn fetch(
+		&self,
+		profession: Profession,
+		cached: Option<&CacheMeta>,
+		report: &mut Report,
+	) -> Result<Option<Fetched>> {
+		let url = self.url(&format!("/wiki/{}", skill_list_page(profession)));
+		let page = match cached {
+			Some(meta) => self.downloader.get_if_modified(
+				&url,
+				meta.etag.as_deref(),
+				meta.last_modified.as_deref(),
+			)?,
+			None => Some(self.downloader.get(&url)?),
+		};
+
+		let page = match page {
+			Some(page) => page,
+			None => return Ok(None),
+		};
+
+		let meta = CacheMeta {
+			etag: page.etag,
+			last_modified: page.last_modified,
+			..CacheMeta::new(url, &page.body)
+		};
+		let mut fetched = parse_page(&page.body, meta, report);
+		io::assign_skill_ids(&mut fetched.skills, &self.skill_ids(report));
+		Ok(Some(fetched))
+	}
+}
+
+/// A directory of skill list pages saved from the wiki, named like the pages themselves,
+/// e.g. `List_of_warrior_skills.html`. If it also has the list of skill IDs, saved as
+/// `Skill_template_format_Skill_list.html`, we take the IDs from there.
+pub struct HtmlDirectory {
+	dir: PathBuf,
+}
+
+impl HtmlDirectory {
+	pub fn new(dir: impl Into<PathBuf>) -> Self {
+		Self { dir: dir.into() }
+	}
+}
+
+impl SkillSource for HtmlDirectory {
+	fn fetch(
+		&self,
+		profession: Profession,
+		_cached: Option<&CacheMeta>,
+		report: &mut Report,
+	) -> Result<Option<Fetched>> {
+		let path = self
+			.dir
+			.join(format!("{}.html", skill_list_page(profession)));
+		let raw_html = fs::read(&path)?;
+		let meta = CacheMeta::new(path.display().to_string(), &raw_html);
+		let mut fetched = parse_page(&raw_html, meta, report);
+
+		let ids_path = self
+			.dir
+			.join(format!("{}.html", SKILL_ID_PAGE.replace('/', "_")));
+		if ids_path.exists() {
+			let ids = io::parse_skill_ids(&fs::read_to_string(ids_path)?);
+			io::assign_skill_ids(&mut fetched.skills, &ids);
+		}
+		Ok(Some(fetched))
+	}
+}
+
+/// A single file with the skills of all professions, either JSON like our data cache,
+/// or CSV with one [`SkillRecord`] per row.
+///
+/// Unlike the wiki pages, these hold the skills just like they end up on the cards,
+/// so allegiance skills have to be listed once for each faction.
+pub struct DataFile {
+	path: PathBuf,
+	/// The skills of all professions, read the first time we're asked for any of them.
+	all: Mutex<Option<Fetched>>,
+}
+
+impl DataFile {
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self {
+			path: path.into(),
+			all: Mutex::new(None),
+		}
+	}
+
+	fn read(&self) -> Result<Fetched> {
+		let contents = fs::read(&self.path)?;
+		Ok(Fetched {
+			skills: self.load(&contents)?,
+			meta: CacheMeta::new(self.path.display().to_string(), &contents),
+		})
+	}
+
+	fn load(&self, contents: &[u8]) -> Result<Vec<Skill>> {
+		match self.path.extension().and_then(|ext| ext.to_str()) {
+			Some("json") => Ok(serde_json::from_slice(contents)?),
+			Some("csv") => csv::Reader::from_reader(contents)
+				.deserialize::<SkillRecord>()
+				.map(|record| Ok(Skill::from(record?)))
+				.collect(),
+			_ => Err(Error::parse(format!(
+				"Don't know how to read {}, it should be .json or .csv",
+				self.path.display()
+			))),
+		}
+	}
+}
+
+impl SkillSource for DataFile {
+	fn fetch(
+		&self,
+		profession: Profession,
+		_cached: Option<&CacheMeta>,
+		_report: &mut Report,
+	) -> Result<Option<Fetched>> {
+		let mut all = self.all.lock().unwrap();
+		let all = match &mut *all {
+			Some(all) => all,
+			None => all.insert(self.read()?),
+		};
+		let skills = all
+			.skills
+			.iter()
+			.filter(|skill| skill.profession == profession)
+			.cloned()
+			.collect();
+		Ok(Some(Fetched {
+			skills,
+			meta: all.meta.clone(),
+		}))
+	}
+}
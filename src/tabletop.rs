@@ -1,49 +1,581 @@
+use crate::assets::CardAssets;
+use crate::back::BackDesign;
+use crate::card;
+use crate::error::{Error, Report, Result};
+use crate::io::Directories;
 use crate::skill;
-use raster::{editor, BlendMode, PositionMode};
-
-pub fn create_tabletop_simulator_decks(skills: &[skill::Skill]) {
-	// Tabletop Simulator wants our cards in batches 10 cards wide, 7 cards high.
-	// Except the bottom right card is a placeholder.
-	// TODO create card background
-	let mut skills = skills.iter().peekable();
-	let mut batch_num = 1;
-	let base = raster::Image {
-		width: 300 * 10,
-		height: 432 * 7,
-		bytes: [0xFF_u8, 0xFF_u8, 0xFF_u8, 0xFF_u8].repeat(300 * 10 * 432 * 7),
-	};
-	let hidden_card = raster::open(&skill::Skill::hidden().card_path()).unwrap();
-
-	while skills.peek().is_some() {
-		let mut deck = base.clone();
-		let batch = skills.by_ref().take(69);
-		for (idx, skill) in batch.enumerate() {
-			let card = raster::open(&skill.card_path()).unwrap();
-			let offset_x = idx % 10 * 300;
-			let offset_y = idx / 10 * 432;
-
-			deck = editor::blend(
-				&deck,
-				&card,
-				BlendMode::Normal,
-				1.0,
-				PositionMode::TopLeft,
-				offset_x as i32,
-				offset_y as i32,
-			)
-			.unwrap();
-		}
-		deck = editor::blend(
-			&deck,
-			&hidden_card,
-			BlendMode::Normal,
-			1.0,
-			PositionMode::BottomRight,
-			0,
-			0,
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, str};
+
+/// Tabletop Simulator wants our cards in batches at most 10 cards wide, 7 cards high.
+pub const SHEET_COLUMNS: u32 = 10;
+pub const SHEET_ROWS: u32 = 7;
+/// Except the bottom right card is a placeholder.
+pub const CARDS_PER_SHEET: usize = (SHEET_COLUMNS * SHEET_ROWS) as usize - 1;
+/// Tabletop Simulator won't load deck sheets wider or higher than this many pixels.
+pub const MAX_SHEET_PIXELS: u32 = 10_000;
+
+/// How cards are laid out on deck sheets.
+///
+/// Read from a TOML file like this one, for small sheets that load quickly:
+///
+/// ```toml
+/// columns = 5
+/// rows = 4
+/// card_width = 200
+/// card_height = 288
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SheetLayout {
+	/// Cards per row, from 2 to 10.
+	pub columns: u32,
+	/// Rows of cards, from 2 to 7.
+	pub rows: u32,
+	/// Width of a card on the sheet in pixels. Rendered cards are scaled to fit.
+	pub card_width: u32,
+	/// Height of a card on the sheet in pixels.
+	pub card_height: u32,
+	/// Put the "Unidentified Skill" card into the bottom right slot, which Tabletop Simulator
+	/// shows for cards in other players' hands. Without it, they see the card back instead,
+	/// and the slot holds a skill like any other.
+	pub hidden_card: bool,
+}
+
+impl Default for SheetLayout {
+	fn default() -> Self {
+		Self {
+			columns: SHEET_COLUMNS,
+			rows: SHEET_ROWS,
+			card_width: card::WIDTH,
+			card_height: card::HEIGHT,
+			hidden_card: true,
+		}
+	}
+}
+
+impl SheetLayout {
+	/// Reads a layout and makes sure Tabletop Simulator can load its sheets.
+	pub fn load(path: &Path) -> Result<Self> {
+		let layout: Self = toml::from_str(&fs::read_to_string(path)?)?;
+		layout.validate()?;
+		Ok(layout)
+	}
+
+	/// Checks the layout against the limits of Tabletop Simulator's custom decks.
+	pub fn validate(&self) -> Result<()> {
+		if !(2..=SHEET_COLUMNS).contains(&self.columns) || !(2..=SHEET_ROWS).contains(&self.rows) {
+			return Err(Error::parse(format!(
+				"Deck sheets need 2 to {} columns and 2 to {} rows, not {}×{}",
+				SHEET_COLUMNS, SHEET_ROWS, self.columns, self.rows
+			)));
+		}
+		if self.card_width == 0 || self.card_height == 0 {
+			return Err(Error::parse("Cards need a width and a height"));
+		}
+		let (width, height) = self.sheet_size();
+		if width > MAX_SHEET_PIXELS || height > MAX_SHEET_PIXELS {
+			return Err(Error::parse(format!(
+				"A {}×{} deck sheet is larger than the {}×{} pixels Tabletop Simulator can load",
+				width, height, MAX_SHEET_PIXELS, MAX_SHEET_PIXELS
+			)));
+		}
+		Ok(())
+	}
+
+	/// How many skills fit on a sheet, leaving room for the hidden card if there is one.
+	pub fn cards_per_sheet(&self) -> usize {
+		(self.columns * self.rows) as usize - self.hidden_card as usize
+	}
+
+	/// Width and height of a whole sheet in pixels.
+	pub fn sheet_size(&self) -> (u32, u32) {
+		(self.columns * self.card_width, self.rows * self.card_height)
+	}
+}
+
+/// How skills are split up into decks.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Grouping {
+	/// A single deck of every skill, called `Deck`.
+	All,
+	/// A deck per profession, like `Warrior Deck`.
+	Profession,
+	/// A deck per campaign, like `Factions Deck`.
+	Campaign,
+	/// A deck per attribute, like `Swordsmanship Deck`, and `No Attribute Deck` for the rest.
+	Attribute,
+	/// A single deck of elite skills, called `Elite Deck`. Other skills are left out.
+	Elites,
+}
+
+impl Grouping {
+	const ALL: [Self; 5] = [
+		Self::All,
+		Self::Profession,
+		Self::Campaign,
+		Self::Attribute,
+		Self::Elites,
+	];
+
+	pub fn iter() -> impl Iterator<Item = Self> {
+		Self::ALL.iter().copied()
+	}
+
+	fn name(self) -> &'static str {
+		match self {
+			Self::All => "all",
+			Self::Profession => "profession",
+			Self::Campaign => "campaign",
+			Self::Attribute => "attribute",
+			Self::Elites => "elites",
+		}
+	}
+
+	/// The name of the deck `skill` goes into, if any.
+	pub fn deck_name(self, skill: &skill::Skill) -> Option<String> {
+		match self {
+			Self::All => Some("Deck".to_owned()),
+			Self::Profession => Some(format!("{} Deck", skill.profession)),
+			Self::Campaign => Some(format!("{} Deck", skill.campaign())),
+			Self::Attribute => match skill.attribute {
+				Some(attribute) => Some(format!("{} Deck", attribute)),
+				None => Some("No Attribute Deck".to_owned()),
+			},
+			Self::Elites if skill.is_elite => Some("Elite Deck".to_owned()),
+			Self::Elites => None,
+		}
+	}
+}
+
+impl str::FromStr for Grouping {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		Self::iter()
+			.find(|grouping| grouping.name().eq_ignore_ascii_case(s))
+			.ok_or_else(|| {
+				format!(
+					"Can't group decks by {}, only by all, profession, campaign, attribute or elites",
+					s
+				)
+			})
+	}
+}
+
+impl fmt::Display for Grouping {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.name())
+	}
+}
+
+/// The order of the cards in a deck.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CardOrder {
+	/// By skill ID. IDs don't change when the wiki shuffles its lists around, so neither do
+	/// the sheets. Skills without an ID go last.
+	Id,
+	/// Alphabetically by name, with variants like `(PvP)` right after the skill itself.
+	Name,
+}
+
+impl CardOrder {
+	fn name(self) -> &'static str {
+		match self {
+			Self::Id => "id",
+			Self::Name => "name",
+		}
+	}
+
+	fn sort(self, skills: &mut [&skill::Skill]) {
+		match self {
+			Self::Id => skills.sort_by_key(|skill| (skill.id.is_none(), skill.id)),
+			Self::Name => skills.sort_by_cached_key(|skill| skill.variant_name()),
+		}
+	}
+}
+
+impl str::FromStr for CardOrder {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		[Self::Id, Self::Name]
+			.iter()
+			.copied()
+			.find(|order| order.name().eq_ignore_ascii_case(s))
+			.ok_or_else(|| format!("Can't sort cards by {}, only by id or name", s))
+	}
+}
+
+impl fmt::Display for CardOrder {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.name())
+	}
+}
+
+/// Puts the rendered cards of `skills` on deck sheets laid out like `layout`, and writes `Deck.json`,
+/// a Tabletop Simulator saved object with a deck of all of them, with a back from `backs`.
+/// Skills whose card we can't open are recorded in `report` and left out.
+pub fn create_tabletop_simulator_decks(
+	skills: &[skill::Skill],
+	dirs: &Directories,
+	layout: &SheetLayout,
+	backs: &BackDesign,
+	report: &mut Report,
+) -> Result<()> {
+	create_grouped_decks(
+		skills,
+		Grouping::All,
+		CardOrder::Id,
+		dirs,
+		layout,
+		backs,
+		report,
+	)
+}
+
+/// Like [`create_tabletop_simulator_decks`], but with a deck for each group of `grouping`,
+/// with its cards in `order`. Each deck is named after its group, with sheets like
+/// `Factions Deck 1.png` and a saved object like `Factions Deck.json`.
+pub fn create_grouped_decks(
+	skills: &[skill::Skill],
+	grouping: Grouping,
+	order: CardOrder,
+	dirs: &Directories,
+	layout: &SheetLayout,
+	backs: &BackDesign,
+	report: &mut Report,
+) -> Result<()> {
+	let assets = CardAssets::load()?;
+	for (name, mut skills) in group_skills(skills, grouping) {
+		order.sort(&mut skills);
+		create_deck_sheets(&skills, &name, dirs, layout, backs, &assets, report)?;
+	}
+	Ok(())
+}
+
+/// Splits `skills` into named decks, in the order their first skill comes up.
+pub fn group_skills(
+	skills: &[skill::Skill],
+	grouping: Grouping,
+) -> Vec<(String, Vec<&skill::Skill>)> {
+	let mut decks: Vec<(String, Vec<&skill::Skill>)> = vec![];
+	for skill in skills {
+		let name = match grouping.deck_name(skill) {
+			Some(name) => name,
+			None => continue,
+		};
+		match decks.iter_mut().find(|(deck, _)| *deck == name) {
+			Some((_, deck)) => deck.push(skill),
+			None => decks.push((name, vec![skill])),
+		}
+	}
+	decks
+}
+
+fn create_deck_sheets(
+	skills: &[&skill::Skill],
+	name: &str,
+	dirs: &Directories,
+	layout: &SheetLayout,
+	backs: &BackDesign,
+	assets: &CardAssets,
+	report: &mut Report,
+) -> Result<()> {
+	layout.validate()?;
+	let hidden_card = hidden_card(dirs, layout)?;
+
+	let per_sheet = layout.cards_per_sheet();
+	let mut batch = Vec::with_capacity(per_sheet);
+	let mut batch_skills = Vec::with_capacity(per_sheet);
+	let mut sheets = vec![];
+	let mut skills = skills.iter().copied().peekable();
+	while let Some(skill) = skills.next() {
+		match open_card(dirs, skill) {
+			Ok(card) => {
+				batch.push(card);
+				batch_skills.push(skill);
+			}
+			Err(err) => report.record(&skill.name, err),
+		}
+		if batch.len() == per_sheet || (skills.peek().is_none() && !batch.is_empty()) {
+			let deck = assemble_sheet(&batch, hidden_card.as_ref(), layout)?;
+			let path = dirs
+				.decks()
+				.join(format!("{} {}.png", name, sheets.len() + 1));
+			deck.save(&path)?;
+			sheets.push(Sheet {
+				path,
+				skills: batch_skills.split_off(0),
+			});
+			batch.clear();
+		}
+	}
+	if !sheets.is_empty() {
+		let back = backs.back_for(&skills_on(&sheets));
+		let back = backs.save(back, &dirs.decks(), assets)?;
+		write_saved_object(name, &sheets, &back, layout, dirs)?;
+	}
+	Ok(())
+}
+
+/// Puts the rendered cards of a single hand, like the skills of a build template,
+/// on a deck sheet of its own called `name`, and returns where we saved it.
+/// Also writes a Tabletop Simulator saved object for the hand next to it.
+pub fn create_hand_sheet(
+	hand: &[&skill::Skill],
+	name: &str,
+	dirs: &Directories,
+	layout: &SheetLayout,
+	backs: &BackDesign,
+	report: &mut Report,
+) -> Result<PathBuf> {
+	layout.validate()?;
+	if hand.len() > layout.cards_per_sheet() {
+		return Err(Error::parse(format!(
+			"A hand of {} cards doesn't fit on a sheet for {}",
+			hand.len(),
+			layout.cards_per_sheet()
+		)));
+	}
+	let hidden_card = hidden_card(dirs, layout)?;
+	let mut cards = vec![];
+	let mut skills = vec![];
+	for skill in hand {
+		match open_card(dirs, skill) {
+			Ok(card) => {
+				cards.push(card);
+				skills.push(*skill);
+			}
+			Err(err) => report.record(&skill.name, err),
+		}
+	}
+	let path = dirs.decks().join(format!("{}.png", name));
+	assemble_sheet(&cards, hidden_card.as_ref(), layout)?.save(&path)?;
+	if !skills.is_empty() {
+		let sheet = Sheet {
+			path: path.clone(),
+			skills,
+		};
+		let back = backs.back_for(&sheet.skills);
+		let back = backs.save(back, &dirs.decks(), &CardAssets::load()?)?;
+		write_saved_object(name, &[sheet], &back, layout, dirs)?;
+	}
+	Ok(path)
+}
+
+/// Lays out cards on a deck sheet like `layout`, row by row, scaling them to its card size,
+/// and puts `hidden_card` into the bottom right slot if there is one.
+pub fn assemble_sheet(
+	cards: &[RgbaImage],
+	hidden_card: Option<&RgbaImage>,
+	layout: &SheetLayout,
+) -> Result<RgbaImage> {
+	let slots = (layout.columns * layout.rows) as usize - hidden_card.is_some() as usize;
+	if cards.len() > slots {
+		return Err(Error::parse(format!(
+			"{} cards don't fit on a deck sheet with room for {}",
+			cards.len(),
+			slots
+		)));
+	}
+	let (width, height) = layout.sheet_size();
+	let mut deck = RgbaImage::from_pixel(width, height, Rgba([0xFF_u8, 0xFF_u8, 0xFF_u8, 0xFF_u8]));
+
+	let last_slot = layout.columns * layout.rows - 1;
+	let slots = cards
+		.iter()
+		.zip(0..)
+		.chain(hidden_card.map(|card| (card, last_slot)));
+	for (card, slot) in slots {
+		let card = fit_card(card, layout);
+		let offset_x = slot % layout.columns * layout.card_width;
+		let offset_y = slot / layout.columns * layout.card_height;
+		imageops::replace(&mut deck, &card, offset_x, offset_y);
+	}
+
+	Ok(deck)
+}
+
+/// Scales `card` to the card size of `layout`, unless it's that size already.
+fn fit_card(card: &RgbaImage, layout: &SheetLayout) -> RgbaImage {
+	if card.dimensions() == (layout.card_width, layout.card_height) {
+		card.clone()
+	} else {
+		imageops::resize(
+			card,
+			layout.card_width,
+			layout.card_height,
+			FilterType::Lanczos3,
 		)
-		.unwrap();
-		raster::save(&deck, &format!("cards/decks/Deck {}.png", batch_num)).unwrap();
-		batch_num += 1;
 	}
 }
+
+fn hidden_card(dirs: &Directories, layout: &SheetLayout) -> Result<Option<RgbaImage>> {
+	if layout.hidden_card {
+		Ok(Some(open_card(dirs, &skill::Skill::hidden())?))
+	} else {
+		Ok(None)
+	}
+}
+
+fn open_card(dirs: &Directories, skill: &skill::Skill) -> Result<RgbaImage> {
+	Ok(image::open(dirs.card_path(skill))?.into_rgba8())
+}
+
+/// A deck sheet we saved, and the skills on it, in the order they're laid out.
+struct Sheet<'a> {
+	path: PathBuf,
+	skills: Vec<&'a skill::Skill>,
+}
+
+/// A file for Tabletop Simulator's Saved Objects folder, holding a single object.
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct SavedObject {
+	save_name: String,
+	object_states: Vec<ObjectState>,
+}
+
+/// A deck, or a lone card, since Tabletop Simulator has no decks of one card.
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ObjectState {
+	name: &'static str,
+	transform: Transform,
+	nickname: String,
+	description: String,
+	#[serde(rename = "CardID", skip_serializing_if = "Option::is_none")]
+	card_id: Option<u32>,
+	#[serde(rename = "DeckIDs", skip_serializing_if = "Vec::is_empty")]
+	deck_ids: Vec<u32>,
+	custom_deck: BTreeMap<u32, CustomDeck>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	contained_objects: Vec<ObjectState>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Transform {
+	pos_x: f32,
+	pos_y: f32,
+	pos_z: f32,
+	rot_x: f32,
+	rot_y: f32,
+	rot_z: f32,
+	scale_x: f32,
+	scale_y: f32,
+	scale_z: f32,
+}
+
+impl Default for Transform {
+	/// Face down in the middle of the table.
+	fn default() -> Self {
+		Self {
+			pos_x: 0.0,
+			pos_y: 1.0,
+			pos_z: 0.0,
+			rot_x: 0.0,
+			rot_y: 180.0,
+			rot_z: 180.0,
+			scale_x: 1.0,
+			scale_y: 1.0,
+			scale_z: 1.0,
+		}
+	}
+}
+
+/// One deck sheet, as Tabletop Simulator knows it.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct CustomDeck {
+	#[serde(rename = "FaceURL")]
+	face_url: String,
+	#[serde(rename = "BackURL")]
+	back_url: String,
+	num_width: u32,
+	num_height: u32,
+	back_is_hidden: bool,
+	unique_back: bool,
+}
+
+fn skills_on<'a>(sheets: &[Sheet<'a>]) -> Vec<&'a skill::Skill> {
+	sheets
+		.iter()
+		.flat_map(|sheet| sheet.skills.iter().copied())
+		.collect()
+}
+
+/// Writes `{name}.json` to the decks directory, a saved object with a deck of all cards on `sheets`.
+/// It points to the sheets and the card back by their local paths, so it only works on this machine.
+fn write_saved_object(
+	name: &str,
+	sheets: &[Sheet],
+	back: &Path,
+	layout: &SheetLayout,
+	dirs: &Directories,
+) -> Result<()> {
+	let back_url = file_url(back)?;
+	let mut custom_decks = BTreeMap::new();
+	let mut cards = vec![];
+	// deck IDs start at 1, and each card's ID is its deck ID followed by two digits for the slot
+	for (deck_id, sheet) in (1..).zip(sheets) {
+		let custom_deck = CustomDeck {
+			face_url: file_url(&sheet.path)?,
+			back_url: back_url.clone(),
+			num_width: layout.columns,
+			num_height: layout.rows,
+			// without a hidden card of our own, show the back to other players
+			back_is_hidden: !layout.hidden_card,
+			unique_back: false,
+		};
+		for (slot, skill) in (0..).zip(&sheet.skills) {
+			cards.push(ObjectState {
+				name: "Card",
+				transform: Transform::default(),
+				nickname: skill.variant_name(),
+				description: skill.type_line(),
+				card_id: Some(deck_id * 100 + slot),
+				deck_ids: vec![],
+				custom_deck: vec![(deck_id, custom_deck.clone())].into_iter().collect(),
+				contained_objects: vec![],
+			});
+		}
+		custom_decks.insert(deck_id, custom_deck);
+	}
+
+	let object = if cards.len() == 1 {
+		cards.remove(0)
+	} else {
+		ObjectState {
+			name: "DeckCustom",
+			transform: Transform::default(),
+			nickname: name.to_owned(),
+			description: String::new(),
+			card_id: None,
+			deck_ids: cards.iter().filter_map(|card| card.card_id).collect(),
+			custom_deck: custom_decks,
+			contained_objects: cards,
+		}
+	};
+	let saved = SavedObject {
+		save_name: name.to_owned(),
+		object_states: vec![object],
+	};
+	let path = dirs.decks().join(format!("{}.json", name));
+	fs::write(path, serde_json::to_string_pretty(&saved)?)?;
+	Ok(())
+}
+
+/// The `file:///` URL Tabletop Simulator needs to load an image from disk.
+fn file_url(path: &Path) -> Result<String> {
+	let path = fs::canonicalize(path)?;
+	let path = path.to_string_lossy();
+	// Windows paths come back as \\?\C:\..., but Tabletop Simulator wants file:///C:/...
+	let path = path.trim_start_matches(r"\\?\").replace('\\', "/");
+	Ok(format!("file:///{}", path.trim_start_matches('/')))
+}
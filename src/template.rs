@@ -0,0 +1,356 @@
+use crate::error::{Error, Result};
+use crate::skill::{Attribute, Profession, Skill};
+
+/// How many skills fit on a skill bar, and so in a template.
+pub const SKILL_SLOTS: usize = 8;
+
+/// Template codes are base64 with the usual alphabet, but fill each character from its lowest bit up.
+const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+/// Newer codes start with this, older ones go straight to the version.
+const SKILL_TEMPLATE_TYPE: u32 = 14;
+const VERSION: u32 = 0;
+
+/// The attributes in order of their template ID, with gaps for the IDs the game doesn't use.
+const ATTRIBUTES: &[Option<Attribute>] = &[
+	Some(Attribute::FastCasting),
+	Some(Attribute::IllusionMagic),
+	Some(Attribute::DominationMagic),
+	Some(Attribute::InspirationMagic),
+	Some(Attribute::BloodMagic),
+	Some(Attribute::DeathMagic),
+	Some(Attribute::SoulReaping),
+	Some(Attribute::Curses),
+	Some(Attribute::AirMagic),
+	Some(Attribute::EarthMagic),
+	Some(Attribute::FireMagic),
+	Some(Attribute::WaterMagic),
+	Some(Attribute::EnergyStorage),
+	Some(Attribute::HealingPrayers),
+	Some(Attribute::SmitingPrayers),
+	Some(Attribute::ProtectionPrayers),
+	Some(Attribute::DivineFavor),
+	Some(Attribute::Strength),
+	Some(Attribute::AxeMastery),
+	Some(Attribute::HammerMastery),
+	Some(Attribute::Swordsmanship),
+	Some(Attribute::Tactics),
+	Some(Attribute::BeastMastery),
+	Some(Attribute::Expertise),
+	Some(Attribute::WildernessSurvival),
+	Some(Attribute::Marksmanship),
+	None,
+	None,
+	None,
+	Some(Attribute::DaggerMastery),
+	Some(Attribute::DeadlyArts),
+	Some(Attribute::ShadowArts),
+	Some(Attribute::Communing),
+	Some(Attribute::RestorationMagic),
+	Some(Attribute::ChannelingMagic),
+	Some(Attribute::CriticalStrikes),
+	Some(Attribute::SpawningPower),
+	Some(Attribute::SpearMastery),
+	Some(Attribute::Command),
+	Some(Attribute::Motivation),
+	Some(Attribute::Leadership),
+	Some(Attribute::ScytheMastery),
+	Some(Attribute::WindPrayers),
+	Some(Attribute::EarthPrayers),
+	Some(Attribute::Mysticism),
+];
+
+/// A build as the game shares it: two professions, attribute ranks, and a skill bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+	pub primary: Profession,
+	pub secondary: Option<Profession>,
+	/// Attributes and the ranks spent on them, without runes or headgear.
+	pub attributes: Vec<(Attribute, u8)>,
+	/// The skill IDs on the bar from left to right, `None` for an empty slot.
+	pub skills: [Option<u16>; SKILL_SLOTS],
+}
+
+impl Template {
+	/// Makes a template from a hand of up to eight skills, e.g. one picked in a draft.
+	///
+	/// The primary profession is the one of the elite skill, or else the one most skills
+	/// belong to. Attributes are left out, players spend their points in-game anyway.
+	pub fn from_hand(hand: &[Skill]) -> Result<Self> {
+		if hand.len() > SKILL_SLOTS {
+			return Err(Error::template(format!(
+				"A skill bar has room for {} skills, not {}",
+				SKILL_SLOTS,
+				hand.len()
+			)));
+		}
+		if hand.iter().filter(|skill| skill.is_elite).count() > 1 {
+			return Err(Error::template("A skill bar can only hold one elite skill"));
+		}
+
+		let mut professions: Vec<(Profession, usize)> = vec![];
+		for skill in hand.iter().filter(|s| s.profession != Profession::Common) {
+			match professions.iter_mut().find(|(p, _)| *p == skill.profession) {
+				Some((_, count)) => *count += 1,
+				None => professions.push((skill.profession, 1)),
+			}
+		}
+		if professions.len() > 2 {
+			return Err(Error::template(format!(
+				"A build has two professions, but these skills need {}",
+				professions.len()
+			)));
+		}
+		let elite = hand.iter().find(|skill| skill.is_elite);
+		professions.sort_by_key(|&(profession, count)| {
+			let is_elite = elite.map(|skill| skill.profession) == Some(profession);
+			(!is_elite, usize::MAX - count)
+		});
+		let mut professions = professions.into_iter().map(|(p, _)| p);
+		let primary = professions.next().ok_or_else(|| {
+			Error::template("Can't tell the primary profession from common skills alone")
+		})?;
+
+		let mut skills = [None; SKILL_SLOTS];
+		for (slot, skill) in skills.iter_mut().zip(hand) {
+			let id = skill.id.ok_or_else(|| {
+				Error::template(format!(
+					"{} has no skill ID, fetch it again to get one",
+					skill.variant_name()
+				))
+			})?;
+			*slot = Some(id);
+		}
+
+		Ok(Self {
+			primary,
+			secondary: professions.next(),
+			attributes: vec![],
+			skills,
+		})
+	}
+
+	/// Reads a template code as copied from the game.
+	pub fn decode(code: &str) -> Result<Self> {
+		let mut bits = BitReader::new(code.trim())?;
+
+		let mut version = bits.read(4)?;
+		if version == SKILL_TEMPLATE_TYPE {
+			version = bits.read(4)?;
+		}
+		if version != VERSION {
+			return Err(Error::template(format!(
+				"Unknown template version {}",
+				version
+			)));
+		}
+
+		let profession_bits = bits.read(2)? * 2 + 4;
+		let primary = bits.read(profession_bits)?;
+		let primary = profession(primary)
+			.ok_or_else(|| Error::template(format!("Unknown profession {}", primary)))?;
+		let secondary = match bits.read(profession_bits)? {
+			0 => None,
+			id => Some(
+				profession(id)
+					.ok_or_else(|| Error::template(format!("Unknown profession {}", id)))?,
+			),
+		};
+
+		let attribute_count = bits.read(4)?;
+		let attribute_bits = bits.read(4)? + 4;
+		let mut attributes = vec![];
+		for _ in 0..attribute_count {
+			let id = bits.read(attribute_bits)?;
+			let attribute = ATTRIBUTES
+				.get(id as usize)
+				.copied()
+				.flatten()
+				.ok_or_else(|| Error::template(format!("Unknown attribute {}", id)))?;
+			attributes.push((attribute, bits.read(4)? as u8));
+		}
+
+		let skill_bits = bits.read(4)? + 8;
+		let mut skills = [None; SKILL_SLOTS];
+		for slot in skills.iter_mut() {
+			*slot = match bits.read(skill_bits)? {
+				0 => None,
+				id => Some(id as u16),
+			};
+		}
+
+		Ok(Self {
+			primary,
+			secondary,
+			attributes,
+			skills,
+		})
+	}
+
+	/// Writes the template code to paste into the game.
+	pub fn encode(&self) -> Result<String> {
+		let mut bits = BitWriter::default();
+		bits.write(SKILL_TEMPLATE_TYPE, 4);
+		bits.write(VERSION, 4);
+
+		let primary = profession_id(self.primary).ok_or_else(|| {
+			Error::template(format!("{} can't be a primary profession", self.primary))
+		})?;
+		let secondary = match self.secondary {
+			Some(profession) => profession_id(profession).ok_or_else(|| {
+				Error::template(format!("{} can't be a secondary profession", profession))
+			})?,
+			None => 0,
+		};
+		// professions take 4, 6, 8 or 10 bits
+		let profession_bits = bits_needed(primary.max(secondary)).max(4);
+		let profession_bits = profession_bits + profession_bits % 2;
+		bits.write((profession_bits - 4) / 2, 2);
+		bits.write(primary, profession_bits);
+		bits.write(secondary, profession_bits);
+
+		if self.attributes.len() > 15 {
+			return Err(Error::template(
+				"A template can't hold more than 15 attributes",
+			));
+		}
+		let attributes = self
+			.attributes
+			.iter()
+			.map(|(attribute, rank)| {
+				let id = ATTRIBUTES
+					.iter()
+					.position(|id| *id == Some(*attribute))
+					.ok_or_else(|| Error::template(format!("{} has no template ID", attribute)))?;
+				if *rank > 15 {
+					return Err(Error::template(format!(
+						"{} can't be at rank {}",
+						attribute, rank
+					)));
+				}
+				Ok((id as u32, *rank as u32))
+			})
+			.collect::<Result<Vec<_>>>()?;
+		let attribute_bits = attributes
+			.iter()
+			.map(|&(id, _)| bits_needed(id))
+			.max()
+			.unwrap_or(0)
+			.max(4);
+		bits.write(attributes.len() as u32, 4);
+		bits.write(attribute_bits - 4, 4);
+		for (id, rank) in attributes {
+			bits.write(id, attribute_bits);
+			bits.write(rank, 4);
+		}
+
+		let skills: Vec<u32> = self
+			.skills
+			.iter()
+			.map(|id| id.unwrap_or(0) as u32)
+			.collect();
+		let skill_bits = skills
+			.iter()
+			.copied()
+			.map(bits_needed)
+			.max()
+			.unwrap_or(0)
+			.max(8);
+		bits.write(skill_bits - 8, 4);
+		for id in skills {
+			bits.write(id, skill_bits);
+		}
+
+		Ok(bits.into_code())
+	}
+
+	/// Looks up the skills on the bar in `pool`, in order and leaving out empty slots.
+	pub fn skills_in<'a>(&self, pool: &'a [Skill]) -> Result<Vec<&'a Skill>> {
+		let mut missing = vec![];
+		let mut skills = vec![];
+		for id in self.skills.iter().flatten() {
+			match pool.iter().find(|skill| skill.id == Some(*id)) {
+				Some(skill) => skills.push(skill),
+				None => missing.push(id.to_string()),
+			}
+		}
+		if !missing.is_empty() {
+			return Err(Error::template(format!(
+				"No skills with ID {} in the card pool",
+				missing.join(", ")
+			)));
+		}
+		Ok(skills)
+	}
+}
+
+/// The ID templates use for `profession`. Common skills don't belong to any.
+fn profession_id(profession: Profession) -> Option<u32> {
+	Profession::iter()
+		.filter(|&p| p != Profession::Common)
+		.position(|p| p == profession)
+		.map(|idx| idx as u32 + 1)
+}
+
+fn profession(id: u32) -> Option<Profession> {
+	Profession::iter().find(|&p| profession_id(p) == Some(id))
+}
+
+fn bits_needed(value: u32) -> u32 {
+	32 - value.leading_zeros()
+}
+
+#[derive(Default)]
+struct BitWriter {
+	bits: Vec<bool>,
+}
+
+impl BitWriter {
+	fn write(&mut self, value: u32, count: u32) {
+		self.bits
+			.extend((0..count).map(|bit| value >> bit & 1 == 1));
+	}
+
+	fn into_code(self) -> String {
+		self.bits
+			.chunks(6)
+			.map(|chunk| {
+				let value = chunk
+					.iter()
+					.enumerate()
+					.fold(0, |value, (bit, &set)| value | (set as usize) << bit);
+				ALPHABET[value] as char
+			})
+			.collect()
+	}
+}
+
+struct BitReader {
+	bits: Vec<bool>,
+	position: usize,
+}
+
+impl BitReader {
+	fn new(code: &str) -> Result<Self> {
+		let mut bits = Vec::with_capacity(code.len() * 6);
+		for c in code.bytes() {
+			let value = ALPHABET.iter().position(|&a| a == c).ok_or_else(|| {
+				Error::template(format!("{:?} doesn't belong in a template code", c as char))
+			})?;
+			bits.extend((0..6).map(|bit| value >> bit & 1 == 1));
+		}
+		Ok(Self { bits, position: 0 })
+	}
+
+	fn read(&mut self, count: u32) -> Result<u32> {
+		let end = self.position + count as usize;
+		let bits = self
+			.bits
+			.get(self.position..end)
+			.ok_or_else(|| Error::template("Template code ended early"))?;
+		self.position = end;
+		Ok(bits
+			.iter()
+			.enumerate()
+			.fold(0, |value, (bit, &set)| value | (set as u32) << bit))
+	}
+}
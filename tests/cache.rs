@@ -0,0 +1,105 @@
+mod common;
+
+use common::{fixtures, skills, Scratch};
+use guild_wars_skill_cards::io::{self, load_cache_meta, CacheUpdate, Directories};
+use guild_wars_skill_cards::{GameMode, Profession, Resource, Skill};
+use std::fs;
+
+fn names(skills: &[Skill]) -> Vec<&str> {
+	skills.iter().map(|s| s.name.as_str()).collect()
+}
+
+#[test]
+fn finds_nothing_between_identical_lists() {
+	assert!(CacheUpdate::between(&skills(), &skills()).is_empty());
+}
+
+#[test]
+fn finds_changed_added_and_removed_skills() {
+	let old = skills();
+	let mut new = skills();
+	new[0].description.push_str(" Balance update.");
+	new[1].resources.push(Resource::Adrenaline(5));
+	new.pop();
+	new.push(Skill::hidden());
+
+	let update = CacheUpdate::between(&old, &new);
+
+	assert_eq!(
+		names(&update.changed),
+		vec!["Ether Prodigy", "Balthazar's Spirit", "Unidentified Skill"]
+	);
+	assert_eq!(update.changed[0].description, new[0].description);
+	assert_eq!(names(&update.removed), vec!["Signet of Capture"]);
+}
+
+#[test]
+fn tells_game_mode_variants_apart() {
+	let mut pve = skills().remove(0);
+	pve.split_by_game_mode = Some(GameMode::PvE);
+	let mut pvp = pve.clone();
+	pvp.split_by_game_mode = Some(GameMode::PvP);
+
+	let update = CacheUpdate::between(&[pve.clone(), pvp], &[pve]);
+
+	assert!(update.changed.is_empty());
+	assert_eq!(update.removed.len(), 1);
+	assert!(update.removed[0].is_pvp_variant());
+}
+
+#[test]
+fn finds_icons_that_moved() {
+	let old = skills();
+	let mut new = skills();
+	new[0].description.push_str(" Balance update.");
+	new[1].icon_url.push_str("?version=2");
+
+	let update = CacheUpdate::between(&old, &new);
+
+	assert_eq!(
+		names(&update.changed),
+		vec!["Ether Prodigy", "Balthazar's Spirit"]
+	);
+	assert_eq!(names(&update.moved_icons), vec!["Balthazar's Spirit"]);
+}
+
+#[test]
+fn removes_the_cards_of_removed_skills() {
+	let scratch = Scratch::new("remove-cards-of");
+	let dirs = scratch.directories();
+	let mut skills = skills();
+	common::blank_cards(&dirs, &skills);
+	let removed = skills.pop().unwrap();
+	let mut pvp = skills[0].clone();
+	pvp.split_by_game_mode = Some(GameMode::PvP);
+
+	io::remove_cards_of(&dirs, &[removed.clone(), pvp], &skills).unwrap();
+
+	assert!(!dirs.card_path(&removed).exists());
+	// the PvE version still needs the card they share
+	assert!(dirs.card_path(&skills[0]).exists());
+}
+
+#[test]
+fn old_caches_have_no_metadata() {
+	let dirs = Directories {
+		cache: fixtures().join("no-cache"),
+		..Directories::default()
+	};
+	assert_eq!(load_cache_meta(&dirs, Profession::Warrior).unwrap(), None);
+}
+
+#[test]
+fn removes_only_the_rendered_cards() {
+	let scratch = Scratch::new("remove-cards");
+	let dirs = scratch.directories();
+	common::blank_cards(&dirs, &skills());
+	fs::write(dirs.cards.join("notes.txt"), "keep me").unwrap();
+
+	io::remove_cards(&dirs).unwrap();
+	io::remove_empty_directory(&dirs.cards).unwrap();
+
+	assert!(skills().iter().all(|s| !dirs.card_path(s).exists()));
+	assert!(dirs.cards.join("notes.txt").exists());
+	assert!(dirs.decks().exists());
+}
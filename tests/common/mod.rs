@@ -0,0 +1,140 @@
+//! Helpers shared by the integration tests: the fixtures, scratch directories, and a tiny
+//! local HTTP server that stands in for the wiki.
+// Not every test uses every helper.
+#![allow(dead_code)]
+
+use guild_wars_skill_cards::io::{self, Directories};
+use guild_wars_skill_cards::Skill;
+use image::{Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub fn fixtures() -> PathBuf {
+	PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/// The fixture cards: two elites, a PvE-only skill and a plain Monk skill.
+pub fn skills() -> Vec<Skill> {
+	let path = fixtures().join("cards/skills.json");
+	serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// A directory of a test's own in the temp dir, removed again when it goes out of scope,
+/// whether the test passed or not.
+pub struct Scratch {
+	root: PathBuf,
+}
+
+impl Scratch {
+	/// Starts out empty, even if an earlier run left something behind.
+	pub fn new(name: &str) -> Self {
+		let root = std::env::temp_dir().join(format!("gw-{}-{}", name, std::process::id()));
+		let _ = fs::remove_dir_all(&root);
+		fs::create_dir_all(&root).unwrap();
+		Self { root }
+	}
+
+	pub fn path(&self) -> &Path {
+		&self.root
+	}
+
+	/// A cache and a cards directory in here.
+	pub fn directories(&self) -> Directories {
+		let dirs = Directories {
+			cache: self.root.join("cache"),
+			cards: self.root.join("cards"),
+		};
+		io::create_directories(&dirs).unwrap();
+		dirs
+	}
+
+	/// A copy of the saved wiki pages in here, for tests that change them.
+	pub fn wiki_pages(&self) -> PathBuf {
+		let pages = self.root.join("pages");
+		fs::create_dir_all(&pages).unwrap();
+		for entry in fs::read_dir(fixtures().join("wiki")).unwrap() {
+			let path = entry.unwrap().path();
+			if path.is_dir() {
+				continue;
+			}
+			fs::copy(&path, pages.join(path.file_name().unwrap())).unwrap();
+		}
+		pages
+	}
+}
+
+impl Drop for Scratch {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.root);
+	}
+}
+
+/// Saves a small blank card for each of `skills`, where the renderer would have put it.
+pub fn blank_cards(dirs: &Directories, skills: &[Skill]) {
+	let card = RgbaImage::from_pixel(30, 43, Rgba([0x80, 0x80, 0x80, 0xFF]));
+	for skill in skills {
+		card.save(dirs.card_path(skill)).unwrap();
+	}
+}
+
+/// Serves `responses` in order, one per connection.
+/// Returns the server's base URL and the head of every request it got.
+pub fn serve(responses: Vec<String>) -> (String, Arc<Mutex<Vec<String>>>) {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let url = format!("http://{}", listener.local_addr().unwrap());
+	let requests = Arc::new(Mutex::new(vec![]));
+	let received = Arc::clone(&requests);
+	thread::spawn(move || {
+		for (stream, response) in listener.incoming().zip(responses) {
+			let mut stream = stream.unwrap();
+			received.lock().unwrap().push(read_head(&stream));
+			stream.write_all(response.as_bytes()).unwrap();
+		}
+	});
+	(url, requests)
+}
+
+/// Serves `pages` by path for as long as the test runs, and 404s everything else.
+pub fn serve_site(pages: HashMap<String, String>) -> String {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let url = format!("http://{}", listener.local_addr().unwrap());
+	thread::spawn(move || {
+		for stream in listener.incoming() {
+			let mut stream = stream.unwrap();
+			let head = read_head(&stream);
+			let path = head.split(' ').nth(1).unwrap_or_default();
+			let answer = match pages.get(path) {
+				Some(page) => response("200 OK", &["Content-Type: text/html"], page),
+				None => response("404 Not Found", &[], ""),
+			};
+			stream.write_all(answer.as_bytes()).unwrap();
+		}
+	});
+	url
+}
+
+fn read_head(stream: &TcpStream) -> String {
+	let mut reader = BufReader::new(stream);
+	let mut head = String::new();
+	while reader.read_line(&mut head).unwrap() > 0 && !head.ends_with("\r\n\r\n") {}
+	head
+}
+
+pub fn response(status: &str, headers: &[&str], body: &str) -> String {
+	let mut response = format!("HTTP/1.1 {}\r\n", status);
+	for header in headers {
+		response.push_str(header);
+		response.push_str("\r\n");
+	}
+	response.push_str(&format!(
+		"Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+		body.len(),
+		body
+	));
+	response
+}
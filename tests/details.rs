@@ -0,0 +1,213 @@
+mod common;
+
+use common::{fixtures, serve_site, Scratch};
+use guild_wars_skill_cards::details::{parse_details, Progression, SkillDetails};
+use guild_wars_skill_cards::download::{DownloadSettings, Downloader};
+use guild_wars_skill_cards::io;
+use guild_wars_skill_cards::source::{HtmlDirectory, Wiki};
+use guild_wars_skill_cards::{Error, Profession, Report, Skill};
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+
+fn page(name: &str) -> String {
+	fs::read_to_string(
+		fixtures()
+			.join("wiki/skills")
+			.join(format!("{}.html", name)),
+	)
+	.unwrap()
+}
+
+fn details(name: &str) -> SkillDetails {
+	parse_details(&page(name)).unwrap()
+}
+
+#[test]
+fn parses_skill_id_and_progression() {
+	let details = details("Healing_Signet");
+
+	assert_eq!(details.id, Some(1));
+	assert_eq!(details.progression.len(), 1);
+	let healing = &details.progression[0];
+	assert_eq!(healing.label, "Healing");
+	assert_eq!(healing.values.len(), 22);
+	assert_eq!(healing.at(0), Some(82.0));
+	assert_eq!(healing.at(15), Some(154.0));
+	assert_eq!(healing.at(21), Some(183.0));
+	assert_eq!(healing.at(22), None);
+}
+
+#[test]
+fn parses_notes_acquisition_and_related_skills() {
+	let details = details("Healing_Signet");
+
+	assert_eq!(
+		details.notes,
+		vec![
+			"The armor penalty applies while activating the skill.",
+			"This skill cannot heal you while you are under the effects of Deep Wound",
+			"It still removes the armor penalty afterwards.",
+		]
+	);
+	assert_eq!(
+		details.acquisition,
+		vec![
+			"Skill trainer in Pre-Searing Ascalon",
+			"Starting skill for new Warriors",
+		]
+	);
+	assert_eq!(details.related_skills, vec!["Mending", "Signet of Rage"]);
+	assert!(self::details("Hundred_Blades").related_skills.is_empty());
+}
+
+#[test]
+fn rejects_incomplete_progressions() {
+	let broken = page("Healing_Signet").replace("<td class=\"attribute-high\">183</td>", "");
+	assert!(parse_details(&broken).is_err());
+}
+
+#[test]
+fn fills_in_descriptions_at_an_attribute_rank() {
+	let mut skill = Skill::hidden();
+	skill.description =
+		"You gain 82...154 Health. You have -40 armor while using this skill.".to_owned();
+	assert_eq!(skill.description_at(12), skill.description);
+
+	skill.details = Some(SkillDetails {
+		progression: vec![Progression {
+			label: "Healing".to_owned(),
+			values: (0..22)
+				.map(|rank| 82.0 + 4.8 * rank as f32)
+				.map(f32::round)
+				.collect(),
+		}],
+		..SkillDetails::default()
+	});
+	assert_eq!(
+		skill.description_at(12),
+		"You gain 140 Health. You have -40 armor while using this skill."
+	);
+	assert_eq!(
+		skill.description_at(0),
+		"You gain 82 Health. You have -40 armor while using this skill."
+	);
+
+	// ranges we can't match to a progression stay as they are
+	skill.description.push_str(" Lasts 5...10 seconds.");
+	assert!(skill.description_at(12).ends_with("Lasts 5...10 seconds."));
+}
+
+#[test]
+fn scrapes_skill_pages_into_the_cache_and_keeps_them_on_refresh() {
+	let scratch = Scratch::new("details-refresh");
+	let dirs = scratch.directories();
+
+	let mut pages = HashMap::new();
+	pages.insert("/wiki/Healing_Signet".to_owned(), page("Healing_Signet"));
+	pages.insert("/wiki/Hundred_Blades".to_owned(), page("Hundred_Blades"));
+	let settings = DownloadSettings {
+		delay: Duration::from_millis(0),
+		retries: 0,
+		..DownloadSettings::default()
+	};
+	let wiki = Wiki::new(serve_site(pages), Downloader::new(settings).unwrap());
+	let source = HtmlDirectory::new(fixtures().join("wiki"));
+
+	let mut report = Report::default();
+	io::build_data_cache(&dirs, &source, Profession::Warrior, false, &mut report).unwrap();
+	io::build_details_cache(&dirs, &wiki, Profession::Warrior, false, &mut report).unwrap();
+
+	// the fixture only has pages for two of the warrior skills
+	assert!(report.failures.iter().any(|(name, _)| name == "Frenzy"));
+	let skills = io::load_skill_cache(&dirs, Profession::Warrior).unwrap();
+	let find = |name: &str| skills.iter().find(|s| s.name == name).unwrap();
+	assert_eq!(
+		find("Hundred Blades").details.as_ref().unwrap().id,
+		Some(381)
+	);
+	assert_eq!(
+		find("Healing Signet").description_at(21),
+		"You gain 183 Health. You have -40 armor while using this skill."
+	);
+	assert_eq!(find("Frenzy").details, None);
+
+	// Refreshing from an unchanged list doesn't touch the cache at all.
+	let update = io::refresh_data_cache(&dirs, &source, Profession::Warrior, &mut report).unwrap();
+	assert!(update.is_empty());
+	assert_eq!(
+		io::load_skill_cache(&dirs, Profession::Warrior).unwrap(),
+		skills
+	);
+
+	// Once the list changes, skills that stayed the same keep their details,
+	// and changed skills lose theirs until we scrape their pages again.
+	let pages = scratch.wiki_pages();
+	let list = pages.join("List_of_warrior_skills.html");
+	let html = fs::read_to_string(&list).unwrap();
+	assert!(html.contains("+10...30 damage"));
+	fs::write(
+		&list,
+		html.replacen("+10...30 damage", "+10...40 damage", 1),
+	)
+	.unwrap();
+
+	let source = HtmlDirectory::new(&pages);
+	let update = io::refresh_data_cache(&dirs, &source, Profession::Warrior, &mut report).unwrap();
+	let changed: Vec<&str> = update.changed.iter().map(|s| s.name.as_str()).collect();
+	assert_eq!(changed, ["Hundred Blades"]);
+	let refreshed = io::load_skill_cache(&dirs, Profession::Warrior).unwrap();
+	let find_refreshed = |name: &str| refreshed.iter().find(|s| s.name == name).unwrap();
+	assert_eq!(find_refreshed("Hundred Blades").details, None);
+	assert!(find_refreshed("Healing Signet").details.is_some());
+	assert_eq!(
+		find_refreshed("Healing Signet").details,
+		find("Healing Signet").details
+	);
+}
+
+#[test]
+fn takes_skill_ids_from_skill_pages_and_checks_they_agree() {
+	let scratch = Scratch::new("details-ids");
+	let pages = scratch.wiki_pages();
+	let id_page = pages.join("Skill_template_format_Skill_list.html");
+	let ids = fs::read_to_string(&id_page).unwrap();
+	fs::remove_file(&id_page).unwrap();
+	let dirs = scratch.directories();
+
+	let mut site = HashMap::new();
+	site.insert("/wiki/Healing_Signet".to_owned(), page("Healing_Signet"));
+	site.insert("/wiki/Hundred_Blades".to_owned(), page("Hundred_Blades"));
+	let settings = DownloadSettings {
+		delay: Duration::from_millis(0),
+		retries: 0,
+		..DownloadSettings::default()
+	};
+	let wiki = Wiki::new(serve_site(site), Downloader::new(settings).unwrap());
+	let source = HtmlDirectory::new(&pages);
+	let mut report = Report::default();
+
+	// Without the list of skill IDs, the skill pages fill them in.
+	io::build_data_cache(&dirs, &source, Profession::Warrior, false, &mut report).unwrap();
+	io::build_details_cache(&dirs, &wiki, Profession::Warrior, false, &mut report).unwrap();
+	let skills = io::load_skill_cache(&dirs, Profession::Warrior).unwrap();
+	let id = |name: &str| skills.iter().find(|s| s.name == name).unwrap().id;
+	assert_eq!(id("Hundred Blades"), Some(381));
+	assert_eq!(id("Healing Signet"), Some(1));
+	assert_eq!(id("Frenzy"), None);
+
+	// A skill whose page disagrees with the list keeps the list's ID, and gets no details.
+	fs::write(&id_page, ids.replacen("<td>381</td>", "<td>382</td>", 1)).unwrap();
+	let mut report = Report::default();
+	io::build_data_cache(&dirs, &source, Profession::Warrior, true, &mut report).unwrap();
+	io::build_details_cache(&dirs, &wiki, Profession::Warrior, false, &mut report).unwrap();
+	let skills = io::load_skill_cache(&dirs, Profession::Warrior).unwrap();
+	let find = |name: &str| skills.iter().find(|s| s.name == name).unwrap();
+	assert_eq!(find("Hundred Blades").id, Some(382));
+	assert_eq!(find("Hundred Blades").details, None);
+	assert!(find("Healing Signet").details.is_some());
+	assert!(report
+		.failures
+		.iter()
+		.any(|(name, err)| name == "Hundred Blades" && matches!(err, Error::Parse(_))));
+}
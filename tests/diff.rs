@@ -0,0 +1,140 @@
+use guild_wars_skill_cards::diff::{load_snapshot, Change, SkillChanges, SkillDiff};
+use guild_wars_skill_cards::{Attribute, GameMode, Resource, Skill};
+use std::path::PathBuf;
+
+fn skills() -> Vec<Skill> {
+	load_snapshot(
+		&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/cards/skills.json"),
+	)
+	.unwrap()
+}
+
+#[test]
+fn reports_nothing_for_identical_snapshots() {
+	let diff = SkillDiff::between(&skills(), &skills());
+	assert!(diff.is_empty());
+	assert_eq!(diff.to_string(), "");
+}
+
+#[test]
+fn reports_added_and_removed_skills() {
+	let old = skills();
+	let mut new = skills();
+	new.remove(0);
+	new.push(Skill::hidden());
+
+	let diff = SkillDiff::between(&old, &new);
+
+	assert_eq!(diff.added, vec!["Unidentified Skill"]);
+	assert_eq!(diff.removed, vec!["Ether Prodigy"]);
+	assert!(diff.changed.is_empty());
+}
+
+#[test]
+fn reports_changed_fields() {
+	let old = skills();
+	let mut new = skills();
+	new[1].resources = vec![Resource::Energy(5)];
+	new[2].is_elite = false;
+	new[2].attribute = None;
+
+	let diff = SkillDiff::between(&old, &new);
+
+	assert_eq!(
+		diff.changed,
+		vec![
+			SkillChanges {
+				skill: "Balthazar's Spirit".to_owned(),
+				changes: vec![Change::Resources {
+					old: old[1].resources.clone(),
+					new: vec![Resource::Energy(5)],
+				}],
+			},
+			SkillChanges {
+				skill: "Hundred Blades".to_owned(),
+				changes: vec![
+					Change::Attribute {
+						old: Some(Attribute::Swordsmanship),
+						new: None,
+					},
+					Change::Elite {
+						old: true,
+						new: false,
+					},
+				],
+			},
+		]
+	);
+	assert_eq!(
+		diff.to_string(),
+		"~ Balthazar's Spirit\n    resources: [Upkeep(-1), Energy(10), Cast(0.25), Recharge(5)] -> [Energy(5)]\n\
+		 ~ Hundred Blades\n    attribute: Swordsmanship -> none\n    elite: true -> false\n"
+	);
+}
+
+#[test]
+fn treats_a_new_pvp_split_as_a_change_and_an_added_skill() {
+	let old = skills();
+	let mut new = skills();
+	new[3].split_by_game_mode = Some(GameMode::PvE);
+	let mut pvp = new[3].clone();
+	pvp.split_by_game_mode = Some(GameMode::PvP);
+	pvp.description = "PvP version.".to_owned();
+	new.push(pvp);
+
+	let diff = SkillDiff::between(&old, &new);
+
+	assert_eq!(diff.added, vec!["Signet of Capture (PvP)"]);
+	assert_eq!(diff.changed.len(), 1);
+	assert_eq!(
+		diff.changed[0].changes,
+		vec![Change::GameModeSplit {
+			old: None,
+			new: Some(GameMode::PvE),
+		}]
+	);
+}
+
+#[test]
+fn follows_renamed_skills_by_their_id() {
+	let mut old = skills();
+	old[0].id = Some(1);
+	let mut new = old.clone();
+	new[0].name = "Ether Savant".to_owned();
+
+	let diff = SkillDiff::between(&old, &new);
+
+	assert!(diff.added.is_empty());
+	assert!(diff.removed.is_empty());
+	assert_eq!(
+		diff.changed,
+		vec![SkillChanges {
+			skill: "Ether Savant".to_owned(),
+			changes: vec![Change::Name {
+				old: "Ether Prodigy".to_owned(),
+				new: "Ether Savant".to_owned(),
+			}],
+		}]
+	);
+}
+
+#[test]
+fn serializes_to_json() {
+	let old = skills();
+	let mut new = skills();
+	new[2].is_elite = false;
+
+	let json = serde_json::to_value(SkillDiff::between(&old, &new)).unwrap();
+
+	assert_eq!(
+		json,
+		serde_json::json!({
+			"added": [],
+			"removed": [],
+			"changed": [{
+				"skill": "Hundred Blades",
+				"changes": [{ "field": "elite", "old": true, "new": false }],
+			}],
+		})
+	);
+}
@@ -0,0 +1,72 @@
+//! Runs the downloader against a tiny local server that answers with canned responses.
+
+mod common;
+
+use common::{response, serve, Scratch};
+use guild_wars_skill_cards::download::{write_atomically, DownloadSettings, Downloader};
+use guild_wars_skill_cards::Error;
+use std::fs;
+use std::time::Duration;
+
+fn downloader() -> Downloader {
+	Downloader::new(DownloadSettings {
+		delay: Duration::from_millis(0),
+		backoff: Duration::from_millis(10),
+		..DownloadSettings::default()
+	})
+	.unwrap()
+}
+
+fn image() -> String {
+	response("200 OK", &["Content-Type: image/jpeg"], "JPEG")
+}
+
+fn unavailable() -> String {
+	response("503 Service Unavailable", &[], "")
+}
+
+#[test]
+fn retries_server_errors() {
+	let (url, requests) = serve(vec![unavailable(), unavailable(), image()]);
+	let download = downloader().get(&url).unwrap().expect_image(&url).unwrap();
+
+	assert_eq!(download.body, b"JPEG");
+	assert_eq!(requests.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn gives_up_on_client_errors() {
+	let (url, requests) = serve(vec![response("404 Not Found", &[], ""), image()]);
+
+	assert!(matches!(downloader().get(&url), Err(Error::Network(_))));
+	assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn rejects_pages_that_are_not_images() {
+	let (url, _) = serve(vec![response(
+		"200 OK",
+		&["Content-Type: text/html"],
+		"nope",
+	)]);
+	let download = downloader().get(&url).unwrap();
+
+	assert!(matches!(download.expect_image(&url), Err(Error::Image(_))));
+}
+
+#[test]
+fn leaves_no_file_behind_when_writing_fails() {
+	let scratch = Scratch::new("download");
+	let dir = scratch.path();
+	let path = dir.join("icon.jpg");
+
+	let result = write_atomically(&path, |temp| {
+		fs::write(temp, b"JP")?;
+		Err(Error::Image("truncated".to_owned()))
+	});
+	assert!(result.is_err());
+	assert_eq!(fs::read_dir(dir).unwrap().count(), 0);
+
+	write_atomically(&path, |temp| Ok(fs::write(temp, b"JPEG")?)).unwrap();
+	assert_eq!(fs::read(&path).unwrap(), b"JPEG");
+}
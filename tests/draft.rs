@@ -0,0 +1,106 @@
+use guild_wars_skill_cards::draft::{run_draft, DraftSettings, Turn};
+use guild_wars_skill_cards::packs::PackRules;
+use guild_wars_skill_cards::{Error, Skill};
+
+/// A pool of `size` distinct cards.
+fn pool(size: usize) -> Vec<Skill> {
+	(0..size)
+		.map(|n| {
+			let mut skill = Skill::hidden();
+			skill.name = format!("Skill {}", n);
+			skill
+		})
+		.collect()
+}
+
+fn settings(real_players: usize, fake_players: usize) -> DraftSettings {
+	DraftSettings {
+		real_players,
+		fake_players,
+		pack_size: 5,
+		rounds: 2,
+		seed: 7,
+		rules: PackRules::default(),
+		professions: vec![],
+	}
+}
+
+fn first_card(_: &Turn) -> Result<usize, Error> {
+	Ok(0)
+}
+
+fn names(skills: &[Skill]) -> Vec<&str> {
+	skills.iter().map(|s| s.name.as_str()).collect()
+}
+
+#[test]
+fn deals_every_card_to_exactly_one_pool_or_the_discards() {
+	let result = run_draft(pool(50), &settings(2, 3), first_card).unwrap();
+
+	assert_eq!(result.pools.len(), 5);
+	for pool in &result.pools {
+		if pool.is_fake {
+			assert!(pool.picks.is_empty());
+		} else {
+			assert_eq!(pool.picks.len(), 10);
+		}
+	}
+	assert_eq!(result.discarded.len(), 30);
+
+	let mut dealt: Vec<&str> = result
+		.pools
+		.iter()
+		.flat_map(|pool| names(&pool.picks))
+		.chain(names(&result.discarded))
+		.collect();
+	dealt.sort_unstable();
+	dealt.dedup();
+	assert_eq!(dealt.len(), 50);
+}
+
+#[test]
+fn deals_the_same_draft_for_the_same_seed() {
+	let first = run_draft(pool(50), &settings(1, 4), first_card).unwrap();
+	let second = run_draft(pool(50), &settings(1, 4), first_card).unwrap();
+	assert_eq!(names(&first.pools[0].picks), names(&second.pools[0].picks));
+	assert_eq!(names(&first.discarded), names(&second.discarded));
+
+	let other_seed = DraftSettings {
+		seed: 8,
+		..settings(1, 4)
+	};
+	let third = run_draft(pool(50), &other_seed, first_card).unwrap();
+	assert_ne!(names(&first.pools[0].picks), names(&third.pools[0].picks));
+}
+
+#[test]
+fn passes_packs_to_the_left() {
+	let mut seen: Vec<(usize, usize, Vec<String>)> = vec![];
+	run_draft(pool(20), &settings(2, 0), |turn| {
+		seen.push((
+			turn.player,
+			turn.pool.len(),
+			turn.pack.iter().map(|s| s.name.clone()).collect(),
+		));
+		Ok(turn.pack.len() - 1)
+	})
+	.unwrap();
+
+	// what player 0 left in their first pack is what player 1 sees next
+	let mut first_pack = seen[0].2.clone();
+	first_pack.pop();
+	assert_eq!((seen[3].0, seen[3].1), (1, 1));
+	assert_eq!(seen[3].2, first_pack);
+}
+
+#[test]
+fn needs_enough_cards_for_every_pack() {
+	let result = run_draft(pool(49), &settings(1, 4), first_card);
+	assert!(matches!(result, Err(Error::Draft(_))));
+}
+
+#[test]
+fn rejects_picks_outside_the_pack() {
+	let result = run_draft(pool(50), &settings(1, 4), |turn| Ok(turn.pack.len()));
+	assert!(matches!(result, Err(Error::Draft(_))));
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::Scratch;
+use guild_wars_skill_cards::details::{Progression, SkillDetails};
+use guild_wars_skill_cards::export::{export_skills, ExportFormat};
+use guild_wars_skill_cards::skill::SkillRecord;
+use guild_wars_skill_cards::{Error, Skill};
+use rusqlite::Connection;
+use std::fs;
+use std::path::Path;
+
+fn skills() -> Vec<Skill> {
+	let mut skills = common::skills();
+	// as if we'd scraped the page of Hundred Blades
+	skills[2].details = Some(SkillDetails {
+		id: skills[2].id,
+		progression: vec![Progression {
+			label: "Damage".to_owned(),
+			values: (0..22).map(|rank| 10.0 + rank as f32 * 4.0 / 3.0).collect(),
+		}],
+		related_skills: vec!["Whirlwind Attack".to_owned()],
+		notes: vec!["Hits up to 3 foes, \"adjacent\" to you; see below.".to_owned()],
+		acquisition: vec![],
+	});
+	skills
+}
+
+#[test]
+fn picks_the_format_by_extension() {
+	let format = |name: &str| ExportFormat::from_path(Path::new(name));
+	assert_eq!(format("skills.csv").unwrap(), ExportFormat::Csv);
+	assert_eq!(format("skills.jsonl").unwrap(), ExportFormat::JsonLines);
+	assert_eq!(format("skills.db").unwrap(), ExportFormat::Sqlite);
+	assert!(matches!(format("skills.xlsx"), Err(Error::Parse(_))));
+}
+
+#[test]
+fn writes_csv_that_reads_back_into_the_same_skills() {
+	let scratch = Scratch::new("export-csv");
+	let path = scratch.path().join("skills.csv");
+	export_skills(&skills(), &path).unwrap();
+
+	let read: Vec<Skill> = csv::Reader::from_path(&path)
+		.unwrap()
+		.deserialize::<SkillRecord>()
+		.map(|record| Skill::from(record.unwrap()))
+		.collect();
+	assert_eq!(read, skills());
+}
+
+#[test]
+fn writes_one_json_object_per_line() {
+	let scratch = Scratch::new("export-jsonl");
+	let path = scratch.path().join("skills.jsonl");
+	export_skills(&skills(), &path).unwrap();
+
+	let contents = fs::read_to_string(&path).unwrap();
+	let records: Vec<SkillRecord> = contents
+		.lines()
+		.map(|line| serde_json::from_str(line).unwrap())
+		.collect();
+	assert_eq!(records.len(), skills().len());
+	assert_eq!(records[2].name, "Hundred Blades");
+	assert_eq!(records[2].adrenaline, Some(8));
+	assert_eq!(records[2].energy, None);
+	assert_eq!(
+		records[2].related_skills,
+		Some(vec!["Whirlwind Attack".to_owned()])
+	);
+	assert_eq!(records[0].notes, None);
+}
+
+#[test]
+fn writes_a_sqlite_table_with_a_column_per_resource_and_detail() {
+	let scratch = Scratch::new("export-sqlite");
+	let path = scratch.path().join("skills.sqlite");
+	export_skills(&skills(), &path).unwrap();
+	// exporting again replaces the old database instead of adding to it
+	export_skills(&skills(), &path).unwrap();
+
+	let connection = Connection::open(&path).unwrap();
+	let count: usize = connection
+		.query_row("SELECT COUNT(*) FROM skills", [], |row| row.get(0))
+		.unwrap();
+	assert_eq!(count, skills().len());
+	let (skill_type, attribute, adrenaline, is_elite): (String, String, u8, bool) = connection
+		.query_row(
+			"SELECT skill_type, attribute, adrenaline, is_elite FROM skills WHERE name = ?1",
+			["Hundred Blades"],
+			|row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+		)
+		.unwrap();
+	assert_eq!(skill_type, "Sword Attack");
+	assert_eq!(attribute, "Swordsmanship");
+	assert_eq!(adrenaline, 8);
+	assert!(is_elite);
+
+	let (progression, acquisition): (String, String) = connection
+		.query_row(
+			"SELECT json_extract(progression, '$[0].label'), acquisition FROM skills WHERE name = ?1",
+			["Hundred Blades"],
+			|row| Ok((row.get(0)?, row.get(1)?)),
+		)
+		.unwrap();
+	assert_eq!(progression, "Damage");
+	assert_eq!(acquisition, "[]");
+	let notes: Option<String> = connection
+		.query_row(
+			"SELECT notes FROM skills WHERE name = ?1",
+			["Ether Prodigy"],
+			|row| row.get(0),
+		)
+		.unwrap();
+	assert_eq!(notes, None);
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use common::fixtures;
+use guild_wars_skill_cards::filter::{Criteria, PoolFilter};
+use guild_wars_skill_cards::{Attribute, Campaign, GameMode, Profession, Skill, SkillType};
+
+/// The fixture cards, plus a PvP version of Hundred Blades.
+fn skills() -> Vec<Skill> {
+	let mut skills = common::skills();
+	let mut pvp = skills[2].clone();
+	pvp.split_by_game_mode = Some(GameMode::PvP);
+	skills[2].split_by_game_mode = Some(GameMode::PvE);
+	skills.push(pvp);
+	skills
+}
+
+fn kept(filter: &PoolFilter) -> Vec<String> {
+	filter
+		.apply(skills())
+		.iter()
+		.map(Skill::variant_name)
+		.collect()
+}
+
+#[test]
+fn keeps_pve_versions_by_default() {
+	assert_eq!(
+		kept(&PoolFilter::default()),
+		vec![
+			"Ether Prodigy",
+			"Balthazar's Spirit",
+			"Hundred Blades",
+			"Signet of Capture"
+		]
+	);
+}
+
+#[test]
+fn can_keep_pvp_versions_instead() {
+	let filter = PoolFilter {
+		game_modes: vec![GameMode::PvP],
+		..PoolFilter::default()
+	};
+	assert!(kept(&filter).contains(&"Hundred Blades (PvP)".to_owned()));
+	assert!(!kept(&filter).contains(&"Hundred Blades".to_owned()));
+}
+
+#[test]
+fn loads_filters_from_toml() {
+	let filter = PoolFilter::load(&fixtures().join("pool_filter.toml")).unwrap();
+
+	assert_eq!(filter.game_modes, vec![GameMode::PvE]);
+	assert_eq!(
+		filter.include.campaigns,
+		vec![Campaign::Core, Campaign::Prophecies]
+	);
+	assert_eq!(filter.exclude.pve_only, Some(true));
+	assert_eq!(kept(&filter), vec!["Balthazar's Spirit"]);
+}
+
+#[test]
+fn includes_only_skills_matching_every_criterion() {
+	let filter = PoolFilter {
+		include: Criteria {
+			professions: vec![Profession::Warrior, Profession::Elementalist],
+			elite: Some(true),
+			attributes: vec![Attribute::Swordsmanship],
+			..Criteria::default()
+		},
+		..PoolFilter::default()
+	};
+	assert_eq!(kept(&filter), vec!["Hundred Blades"]);
+}
+
+#[test]
+fn excludes_skills_matching_any_criterion() {
+	let filter = PoolFilter {
+		exclude: Criteria {
+			professions: vec![Profession::Monk],
+			quest_reward: Some(false),
+			..Criteria::default()
+		},
+		..PoolFilter::default()
+	};
+	assert!(kept(&filter).is_empty());
+}
+
+#[test]
+fn matches_campaigns_by_their_wiki_names() {
+	let filter: PoolFilter = toml::from_str("[include]\ncampaigns = [\"Factions\"]").unwrap();
+	assert_eq!(kept(&filter), vec!["Ether Prodigy"]);
+	assert_eq!(
+		"eye of the north".parse::<Campaign>(),
+		Ok(Campaign::EyeOfTheNorth)
+	);
+}
+
+#[test]
+fn matches_skill_types_and_attributes_by_their_wiki_names() {
+	let filter: PoolFilter = toml::from_str(
+		"[include]\nskill_types = [\"Enchantment Spell\"]\n\n[exclude]\nattributes = [\"Energy Storage\"]",
+	)
+	.unwrap();
+	assert_eq!(
+		filter.include.skill_types,
+		vec![SkillType::EnchantmentSpell]
+	);
+	assert_eq!(filter.exclude.attributes, vec![Attribute::EnergyStorage]);
+	assert_eq!(kept(&filter), vec!["Balthazar's Spirit"]);
+
+	let typo = toml::from_str::<PoolFilter>("[include]\nattributes = [\"Swordmanship\"]");
+	assert!(typo.is_err());
+}
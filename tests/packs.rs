@@ -0,0 +1,229 @@
+mod common;
+
+use common::{fixtures, Scratch};
+use guild_wars_skill_cards::draft::{run_draft, DraftSettings};
+use guild_wars_skill_cards::packs::PackRules;
+use guild_wars_skill_cards::{Error, Profession, Skill};
+use std::fs;
+
+/// `copies` distinct copies of each fixture card: two elites, a PvE-only skill and a plain Monk skill.
+fn pool(copies: usize) -> Vec<Skill> {
+	let skills = common::skills();
+	(0..copies)
+		.flat_map(|n| {
+			skills.iter().map(move |skill| {
+				let mut skill = skill.clone();
+				skill.name = format!("{} {}", skill.name, n);
+				skill
+			})
+		})
+		.collect()
+}
+
+/// `count` distinct plain skills of `profession`, elite if `is_elite`.
+fn skills_of(profession: Profession, is_elite: bool, count: usize) -> Vec<Skill> {
+	let plain = pool(1)
+		.into_iter()
+		.find(|s| !s.is_elite && !s.is_pve_only())
+		.unwrap();
+	(0..count)
+		.map(|n| {
+			let mut skill = plain.clone();
+			skill.name = format!("{} {} {}", profession, is_elite, n);
+			skill.profession = profession;
+			skill.is_elite = is_elite;
+			skill
+		})
+		.collect()
+}
+
+fn rules() -> PackRules {
+	PackRules::load(&fixtures().join("pack_rules.toml")).unwrap()
+}
+
+#[test]
+fn loads_rules_from_toml() {
+	assert_eq!(
+		rules(),
+		PackRules {
+			max_elites: Some(1),
+			max_pve_only: Some(1),
+			min_own_profession: 2,
+		}
+	);
+}
+
+#[test]
+fn rejects_unknown_rules() {
+	let scratch = Scratch::new("packs-bad-rules");
+	let path = scratch.path().join("pack_rules.toml");
+	fs::write(&path, "max_elite = 1\n").unwrap();
+	assert!(matches!(PackRules::load(&path), Err(Error::Parse(_))));
+}
+
+#[test]
+fn deals_packs_that_follow_the_rules() {
+	let rules = rules();
+	let mut pool = pool(10);
+
+	for _ in 0..4 {
+		let pack = rules
+			.deal_pack(&mut pool, 4, Some(Profession::Monk))
+			.unwrap();
+		assert_eq!(pack.len(), 4);
+		assert!(pack.iter().filter(|s| s.is_elite).count() <= 1);
+		assert!(pack.iter().filter(|s| s.is_pve_only()).count() <= 1);
+		assert!(
+			pack.iter()
+				.filter(|s| s.profession == Profession::Monk)
+				.count() >= 2
+		);
+	}
+	assert_eq!(pool.len(), 40 - 16);
+}
+
+#[test]
+fn checks_the_pool_before_dealing() {
+	let rules = rules();
+	let pool = pool(10);
+
+	// two Warrior skills for each of 6 packs, but only 10 Warrior skills to go around
+	let warriors = vec![Some(Profession::Warrior); 6];
+	assert!(matches!(
+		rules.validate(&pool, 4, &warriors),
+		Err(Error::Draft(_))
+	));
+	// packs of 5 need 3 plain skills each, and there are only 10
+	assert!(matches!(
+		rules.validate(&pool, 5, &[None; 4]),
+		Err(Error::Draft(_))
+	));
+	assert!(rules.validate(&pool, 4, &[None; 4]).is_ok());
+	assert!(matches!(
+		rules.validate(&pool, 1, &[Some(Profession::Monk)]),
+		Err(Error::Draft(_))
+	));
+
+	// enough Monk skills, but only one of them fits in a pack with the elite
+	let elites = PackRules {
+		max_elites: Some(1),
+		max_pve_only: None,
+		min_own_profession: 2,
+	};
+	let mut pool = skills_of(Profession::Monk, true, 4);
+	pool.extend(skills_of(Profession::Warrior, false, 4));
+	assert!(matches!(
+		elites.validate(&pool, 2, &[Some(Profession::Monk)]),
+		Err(Error::Draft(_))
+	));
+	assert!(matches!(
+		elites.deal_pack(&mut pool, 2, Some(Profession::Monk)),
+		Err(Error::Draft(_))
+	));
+	assert_eq!(pool.len(), 8);
+}
+
+#[test]
+fn drafts_with_pack_rules() {
+	let settings = DraftSettings {
+		real_players: 1,
+		fake_players: 1,
+		pack_size: 4,
+		rounds: 2,
+		seed: 3,
+		rules: rules(),
+		professions: vec![Profession::Monk],
+	};
+	let mut first_packs = vec![];
+	run_draft(pool(10), &settings, |turn| {
+		// the pack the player opened themselves
+		if turn.pack.len() == 4 {
+			first_packs.push(turn.pack.to_vec());
+		}
+		Ok(0)
+	})
+	.unwrap();
+
+	assert_eq!(first_packs.len(), 2);
+	for pack in first_packs {
+		assert!(
+			pack.iter()
+				.filter(|s| s.profession == Profession::Monk)
+				.count() >= 2
+		);
+	}
+}
+
+/// Drafts `pool` with every seed from 0 to 50, checking the pack the Monk player opens each round.
+fn draft_tight_pool(pool: Vec<Skill>, settings: DraftSettings) {
+	for seed in 0..50 {
+		let settings = DraftSettings {
+			seed,
+			..settings.clone()
+		};
+		let mut opened = vec![];
+		run_draft(pool.clone(), &settings, |turn| {
+			if turn.pack.len() == settings.pack_size {
+				opened.push(turn.pack.to_vec());
+			}
+			Ok(0)
+		})
+		.unwrap_or_else(|err| panic!("seed {}: {}", seed, err));
+
+		assert_eq!(opened.len(), settings.rounds);
+		for pack in opened {
+			let monks = pack
+				.iter()
+				.filter(|s| s.profession == Profession::Monk)
+				.count();
+			assert!(monks >= settings.rules.min_own_profession);
+			if let Some(max) = settings.rules.max_elites {
+				assert!(pack.iter().filter(|s| s.is_elite).count() <= max);
+			}
+		}
+	}
+}
+
+#[test]
+fn keeps_enough_profession_skills_for_every_round() {
+	let mut pool = skills_of(Profession::Monk, false, 2);
+	pool.extend(skills_of(Profession::Warrior, false, 6));
+	let settings = DraftSettings {
+		real_players: 1,
+		fake_players: 1,
+		pack_size: 2,
+		rounds: 2,
+		seed: 0,
+		rules: PackRules {
+			max_elites: None,
+			max_pve_only: None,
+			min_own_profession: 1,
+		},
+		professions: vec![Profession::Monk],
+	};
+	draft_tight_pool(pool, settings);
+}
+
+#[test]
+fn keeps_profession_skills_that_fit_the_caps_for_every_round() {
+	// the only way to deal this: every Monk pack gets one elite and one plain Monk skill,
+	// and every pack one elite
+	let mut pool = skills_of(Profession::Monk, false, 3);
+	pool.extend(skills_of(Profession::Monk, true, 3));
+	pool.extend(skills_of(Profession::Warrior, false, 9));
+	pool.extend(skills_of(Profession::Warrior, true, 3));
+	let settings = DraftSettings {
+		real_players: 1,
+		fake_players: 1,
+		pack_size: 3,
+		rounds: 3,
+		seed: 0,
+		rules: PackRules {
+			max_elites: Some(1),
+			max_pve_only: None,
+			min_own_profession: 2,
+		},
+		professions: vec![Profession::Monk],
+	};
+	draft_tight_pool(pool, settings);
+}
@@ -0,0 +1,279 @@
+use guild_wars_skill_cards::io::{parse_skills, touch_up_skills};
+use guild_wars_skill_cards::{
+	Attribute, Campaign, GameMode, Profession, Report, Resource, Skill, SkillType,
+};
+use std::fs;
+use std::path::PathBuf;
+
+fn fixture(profession: Profession) -> String {
+	let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+		.join("tests/fixtures/wiki")
+		.join(format!(
+			"List_of_{}_skills.html",
+			profession.to_string().to_lowercase()
+		));
+	fs::read_to_string(path).unwrap()
+}
+
+/// Parses a fixture the way `build_data_cache` does, and makes sure nothing went wrong.
+fn parse(profession: Profession) -> Vec<Skill> {
+	let mut report = Report::default();
+	let skills = touch_up_skills(parse_skills(&fixture(profession), &mut report));
+	assert!(report.is_empty(), "{}", report);
+	skills
+}
+
+fn find<'a>(skills: &'a [Skill], name: &str) -> &'a Skill {
+	skills
+		.iter()
+		.find(|s| s.name == name)
+		.unwrap_or_else(|| panic!("{} is missing", name))
+}
+
+#[test]
+fn every_profession_page_parses() {
+	for profession in Profession::iter() {
+		let skills = parse(profession);
+		assert!(!skills.is_empty(), "No {} skills", profession);
+		assert!(skills.iter().all(|s| s.profession == profession));
+	}
+}
+
+#[test]
+fn parses_basic_fields() {
+	let skills = parse(Profession::Warrior);
+	let skill = find(&skills, "Healing Signet");
+
+	assert_eq!(
+		skill.icon_url,
+		"/images/thumb/6/6a/Healing_Signet.jpg/50px-Healing_Signet.jpg"
+	);
+	assert_eq!(skill.skill_type(), SkillType::Signet);
+	assert_eq!(
+		skill.description,
+		"You gain 82...154 Health. You have -40 armor while using this skill."
+	);
+	assert_eq!(skill.attribute, Some(Attribute::Tactics));
+	assert_eq!(skill.campaign(), Campaign::Core);
+	assert_eq!(
+		skill.resources,
+		vec![Resource::Cast(2.0), Resource::Recharge(4)]
+	);
+	assert!(!skill.is_elite);
+	assert!(!skill.is_quest_reward());
+	assert!(!skill.is_pve_only());
+	assert_eq!(skill.split_by_game_mode, None);
+	assert_eq!(skill.type_line(), "Warrior Signet (Tactics)");
+}
+
+#[test]
+fn parses_elite_skills() {
+	let skills = parse(Profession::Warrior);
+	let skill = find(&skills, "Hundred Blades");
+
+	assert!(skill.is_elite);
+	assert_eq!(skill.skill_type(), SkillType::SwordAttack);
+	assert!(skill.skill_type().is_attack());
+	assert_eq!(
+		skill.type_line(),
+		"Elite Warrior Sword Attack (Swordsmanship)"
+	);
+	assert!(!find(&skills, "Sever Artery").is_elite);
+}
+
+#[test]
+fn parses_missing_attribute_and_quest_reward() {
+	let skills = parse(Profession::Warrior);
+
+	assert_eq!(find(&skills, "Frenzy").attribute, None);
+	assert_eq!(find(&skills, "Frenzy").type_line(), "Warrior Stance");
+	assert!(find(&skills, "Defy Pain").is_quest_reward());
+}
+
+#[test]
+fn parses_profession_specific_resources() {
+	let warrior = parse(Profession::Warrior);
+	assert_eq!(
+		find(&warrior, "Sever Artery").resources,
+		vec![Resource::Adrenaline(4)]
+	);
+
+	let necromancer = parse(Profession::Necromancer);
+	assert_eq!(
+		find(&necromancer, "Blood is Power").resources,
+		vec![
+			Resource::Sacrifice(33),
+			Resource::Energy(5),
+			Resource::Cast(0.25),
+			Resource::Recharge(2),
+		]
+	);
+
+	let monk = parse(Profession::Monk);
+	assert_eq!(
+		find(&monk, "Balthazar's Spirit").resources,
+		vec![
+			Resource::Upkeep(-1),
+			Resource::Energy(10),
+			Resource::Cast(1.0),
+			Resource::Recharge(5),
+		]
+	);
+	assert_eq!(
+		find(&monk, "Word of Healing").resources,
+		vec![
+			Resource::Energy(5),
+			Resource::Cast(0.75),
+			Resource::Recharge(3),
+		]
+	);
+
+	let elementalist = parse(Profession::Elementalist);
+	assert_eq!(
+		find(&elementalist, "Blinding Surge").resources[0],
+		Resource::Overcast(5)
+	);
+	// Over the Limit is the one Elementalist skill with an upkeep instead of overcast.
+	assert_eq!(
+		find(&elementalist, "Over the Limit").resources[0],
+		Resource::Upkeep(-1)
+	);
+
+	let common = parse(Profession::Common);
+	assert_eq!(
+		find(&common, "Brawling Headbutt").resources,
+		vec![Resource::Adrenaline(7)]
+	);
+}
+
+#[test]
+fn ignores_profession_specific_column_without_resource() {
+	let ranger = parse(Profession::Ranger);
+	assert_eq!(
+		find(&ranger, "Troll Unguent").resources,
+		vec![
+			Resource::Energy(5),
+			Resource::Cast(3.0),
+			Resource::Recharge(10),
+		]
+	);
+}
+
+#[test]
+fn splits_skills_by_game_mode() {
+	let skills = parse(Profession::Warrior);
+	let variants: Vec<&Skill> = skills.iter().filter(|s| s.name == "Defy Pain").collect();
+
+	assert_eq!(variants.len(), 2);
+	assert_eq!(variants[0].split_by_game_mode, Some(GameMode::PvE));
+	assert!(!variants[0].is_pvp_variant());
+	assert_eq!(variants[1].split_by_game_mode, Some(GameMode::PvP));
+	assert!(variants[1].is_pvp_variant());
+	assert!(variants[1].description.starts_with("For 8 seconds"));
+}
+
+#[test]
+fn codex_variant_does_not_split_regular_charm_animal() {
+	let skills = parse(Profession::Ranger);
+	let variants: Vec<&Skill> = skills.iter().filter(|s| s.name == "Charm Animal").collect();
+
+	assert_eq!(variants.len(), 2);
+	assert_eq!(variants[0].split_by_game_mode, None);
+	assert_eq!(variants[1].split_by_game_mode, Some(GameMode::Codex));
+
+	let glass_arrows = skills.iter().filter(|s| s.name == "Glass Arrows");
+	let modes: Vec<Option<GameMode>> = glass_arrows.map(|s| s.split_by_game_mode).collect();
+	assert_eq!(modes, vec![Some(GameMode::PvE), Some(GameMode::PvP)]);
+}
+
+#[test]
+fn detects_pve_only_skills() {
+	let common = parse(Profession::Common);
+	assert!(find(&common, "Ebon Vanguard Assassin Support").is_pve_only());
+	assert!(find(&common, "Ebon Vanguard Assassin Support").is_quest_reward());
+	assert!(!find(&common, "Resurrection Signet").is_pve_only());
+
+	// These aren't tied to a title track, so we know them by name.
+	let assassin = parse(Profession::Assassin);
+	assert!(find(&assassin, "Shadow Theft").is_pve_only());
+	assert!(!find(&assassin, "Jagged Strike").is_pve_only());
+	let paragon = parse(Profession::Paragon);
+	assert!(find(&paragon, "Heroic Refrain").is_pve_only());
+}
+
+#[test]
+fn keeps_quotes_in_shout_names() {
+	let skills = parse(Profession::Paragon);
+	let skill = find(&skills, "\"Fall Back!\"");
+
+	assert_eq!(skill.skill_type(), SkillType::Shout);
+	assert_eq!(skill.type_line(), "Paragon Shout (Command)");
+}
+
+#[test]
+fn duplicates_allegiance_skills_into_kurzick_and_luxon_variants() {
+	let skills = parse(Profession::Common);
+	let variants: Vec<&Skill> = skills
+		.iter()
+		.filter(|s| s.name == "\"Save Yourselves!\"")
+		.collect();
+
+	assert_eq!(variants.len(), 2);
+	assert_eq!(variants[0].attribute, Some(Attribute::KurzickRank));
+	assert_eq!(variants[1].attribute, Some(Attribute::LuxonRank));
+	assert!(variants[0].file_name().ends_with("-Kurzick"));
+	assert!(variants[1].file_name().ends_with("-Luxon"));
+	assert_eq!(variants[0].type_line(), "PvE Shout (Kurzick)");
+	assert_eq!(variants[0].icon_url, variants[1].icon_url);
+}
+
+#[test]
+fn reports_broken_rows_and_keeps_the_rest() {
+	let page = fixture(Profession::Monk).replace(
+		"<tr data-name=\"Protective Spirit\" style=\"background: #CCEEFF;\">",
+		"<tr data-name=\"Protective Spirit\" style=\"background: #123456;\">",
+	);
+	let mut report = Report::default();
+	let skills = parse_skills(&page, &mut report);
+
+	assert_eq!(report.failures.len(), 1);
+	assert_eq!(report.failures[0].0, "Protective Spirit");
+	assert_eq!(skills.len(), 2);
+	assert!(skills.iter().all(|s| s.name != "Protective Spirit"));
+}
+
+#[test]
+fn reports_unknown_skill_types_and_attributes() {
+	let page = fixture(Profession::Monk)
+		.replace(
+			"title=\"Enchantment Spell\">Enchantment Spell</a>. For 5...11",
+			"title=\"Enchantment Spell\">Enchanted Spell</a>. For 5...11",
+		)
+		.replace("<td>Smiting Prayers</td>", "<td>Smiting Prayer</td>");
+	let mut report = Report::default();
+	let skills = parse_skills(&page, &mut report);
+
+	assert_eq!(report.failures.len(), 2);
+	assert!(report.failures[0].1.to_string().contains("Enchanted Spell"));
+	assert!(report.failures[1].1.to_string().contains("Smiting Prayer"));
+	assert_eq!(skills.len(), 1);
+}
+
+#[test]
+fn parses_skill_types_and_attributes_by_their_wiki_names() {
+	assert_eq!("Hex Spell".parse(), Ok(SkillType::HexSpell));
+	assert!(SkillType::HexSpell.is_spell());
+	assert_eq!(
+		"Ebon Vanguard rank".parse(),
+		Ok(Attribute::EbonVanguardRank)
+	);
+	assert!(Attribute::EbonVanguardRank.is_rank());
+	assert_eq!(Attribute::EbonVanguardRank.short_name(), "Ebon Vanguard");
+	assert!("???".parse::<Attribute>().is_err());
+	for skill_type in SkillType::iter() {
+		assert_eq!(skill_type.to_string().parse(), Ok(skill_type));
+	}
+	for attribute in Attribute::iter() {
+		assert_eq!(attribute.to_string().parse(), Ok(attribute));
+	}
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use common::{blank_cards, skills, Scratch};
+use guild_wars_skill_cards::back::BackDesign;
+use guild_wars_skill_cards::print::{self, CardSize, Paper, PrintSettings};
+use guild_wars_skill_cards::{Error, Report};
+use std::fs;
+
+/// Small enough to render quickly.
+fn draft_settings() -> PrintSettings {
+	PrintSettings {
+		dpi: 72,
+		..PrintSettings::default()
+	}
+}
+
+#[test]
+fn fits_cards_on_the_page() {
+	let settings = PrintSettings::default();
+	assert_eq!(settings.grid().unwrap(), (3, 3));
+
+	let letter = PrintSettings {
+		paper: Paper::Letter,
+		..PrintSettings::default()
+	};
+	// a third row would leave no room for cut marks above and below the cards
+	assert_eq!(letter.grid().unwrap(), (3, 2));
+	let narrow_margin = PrintSettings {
+		margin: 3.0,
+		..letter.clone()
+	};
+	assert_eq!(narrow_margin.grid().unwrap(), (3, 3));
+
+	let bleed = PrintSettings {
+		bleed: 3.0,
+		..PrintSettings::default()
+	};
+	assert_eq!(bleed.grid().unwrap(), (2, 2));
+	let no_marks = PrintSettings {
+		cut_marks: false,
+		..bleed
+	};
+	assert_eq!(no_marks.grid().unwrap(), (2, 3));
+
+	let tarot = PrintSettings {
+		card_size: CardSize::Tarot,
+		..PrintSettings::default()
+	};
+	assert_eq!(tarot.grid().unwrap(), (2, 2));
+	assert_eq!(tarot.cards_per_page().unwrap(), 4);
+}
+
+#[test]
+fn rejects_settings_that_dont_fit() {
+	let margin = PrintSettings {
+		margin: 80.0,
+		..PrintSettings::default()
+	};
+	assert!(matches!(margin.grid(), Err(Error::Print(_))));
+
+	let bleed = PrintSettings {
+		bleed: -1.0,
+		..PrintSettings::default()
+	};
+	assert!(matches!(bleed.grid(), Err(Error::Print(_))));
+
+	let dpi = PrintSettings {
+		dpi: 0,
+		..PrintSettings::default()
+	};
+	assert!(matches!(dpi.grid(), Err(Error::Print(_))));
+}
+
+#[test]
+fn parses_paper_and_card_sizes() {
+	assert_eq!("a4".parse::<Paper>().unwrap(), Paper::A4);
+	assert_eq!("LETTER".parse::<Paper>().unwrap(), Paper::Letter);
+	assert!("A3".parse::<Paper>().is_err());
+	assert_eq!("Tarot".parse::<CardSize>().unwrap(), CardSize::Tarot);
+	assert!("bridge".parse::<CardSize>().is_err());
+}
+
+#[test]
+fn writes_a_pdf_of_the_cards() {
+	let skills = skills();
+	let scratch = Scratch::new("print-pdf");
+	let dirs = scratch.directories();
+	blank_cards(&dirs, &skills);
+	let path = dirs.cards.join("Cube.pdf");
+	let settings = PrintSettings {
+		card_size: CardSize::Tarot,
+		..draft_settings()
+	};
+	let mut report = Report::default();
+	let pages = print::create_print_and_play(
+		&skills,
+		&dirs,
+		&settings,
+		&BackDesign::default(),
+		&path,
+		&mut report,
+	)
+	.unwrap();
+	assert!(report.is_empty(), "{}", report);
+	assert_eq!(pages, skills.len().div_ceil(4));
+	assert!(fs::read(&path).unwrap().starts_with(b"%PDF"));
+}
+
+#[test]
+fn follows_each_page_with_its_backs_for_duplex() {
+	let skills = skills();
+	let scratch = Scratch::new("print-duplex");
+	let dirs = scratch.directories();
+	blank_cards(&dirs, &skills);
+	let path = dirs.cards.join("Cube.pdf");
+	let settings = PrintSettings {
+		card_size: CardSize::Tarot,
+		duplex: true,
+		..draft_settings()
+	};
+	let mut report = Report::default();
+	let pages = print::create_print_and_play(
+		&skills,
+		&dirs,
+		&settings,
+		&BackDesign::default(),
+		&path,
+		&mut report,
+	)
+	.unwrap();
+	assert!(report.is_empty(), "{}", report);
+	assert_eq!(pages, 2 * skills.len().div_ceil(4));
+}
+
+#[test]
+fn reports_missing_cards() {
+	let skills = skills();
+	let scratch = Scratch::new("print-missing");
+	let dirs = scratch.directories();
+	blank_cards(&dirs, &skills);
+	fs::remove_file(dirs.card_path(&skills[0])).unwrap();
+	let path = dirs.cards.join("Cube.pdf");
+	let mut report = Report::default();
+	print::create_print_and_play(
+		&skills,
+		&dirs,
+		&draft_settings(),
+		&BackDesign::default(),
+		&path,
+		&mut report,
+	)
+	.unwrap();
+	assert_eq!(report.failures.len(), 1);
+	assert_eq!(report.failures[0].0, skills[0].name);
+	assert!(path.exists());
+}
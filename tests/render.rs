@@ -0,0 +1,158 @@
+//! Renders a few representative cards and card backs, and compares them against golden images.
+//!
+//! After a deliberate layout change, regenerate the golden images with
+//! `UPDATE_GOLDENS=1 cargo test --test render` and check the new images before committing them.
+
+use guild_wars_skill_cards::assets::CardAssets;
+use guild_wars_skill_cards::back::{Back, BackDesign};
+use guild_wars_skill_cards::{card, Campaign, Profession, Skill};
+use image::{Pixel, RgbaImage};
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+/// How far apart a single color channel may be before we count a pixel as different.
+const CHANNEL_TOLERANCE: u8 = 16;
+/// What share of all pixels may differ before we call it a regression.
+const MAX_DIFFERENT_PIXELS: f64 = 0.002;
+
+fn fixtures() -> PathBuf {
+	PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/cards")
+}
+
+fn skill(name: &str) -> Skill {
+	let raw = fs::read_to_string(fixtures().join("skills.json")).unwrap();
+	let skills: Vec<Skill> = serde_json::from_str(&raw).unwrap();
+	skills.into_iter().find(|s| s.name == name).unwrap()
+}
+
+fn count_different_pixels(actual: &RgbaImage, expected: &RgbaImage) -> usize {
+	actual
+		.pixels()
+		.zip(expected.pixels())
+		.filter(|(a, e)| {
+			a.channels()
+				.iter()
+				.zip(e.channels())
+				.any(|(a, e)| (*a as i16 - *e as i16).abs() > CHANNEL_TOLERANCE as i16)
+		})
+		.count()
+}
+
+fn render(skill: &Skill) -> guild_wars_skill_cards::Result<RgbaImage> {
+	let icon = fixtures()
+		.join("icons")
+		.join(format!("{}.jpg", skill.file_name()));
+	card::render_card(skill, &icon, &CardAssets::load().unwrap())
+}
+
+fn check_golden(name: &str, skill: &Skill) {
+	let actual = render(skill).unwrap();
+	check_image(name, &actual);
+}
+
+fn check_back(name: &str, back: Back) {
+	let actual = BackDesign::default()
+		.render(back, &CardAssets::load().unwrap())
+		.unwrap();
+	check_image(name, &actual);
+}
+
+fn check_image(name: &str, actual: &RgbaImage) {
+	let golden = fixtures().join("golden").join(format!("{}.png", name));
+
+	if env::var_os("UPDATE_GOLDENS").is_some() {
+		actual.save(&golden).unwrap();
+		return;
+	}
+
+	let expected = image::open(&golden)
+		.unwrap_or_else(|err| panic!("Can't open {}: {}", golden.display(), err))
+		.into_rgba8();
+	assert_eq!(actual.dimensions(), expected.dimensions());
+
+	let different = count_different_pixels(actual, &expected);
+	let allowed = (actual.len() / 4) as f64 * MAX_DIFFERENT_PIXELS;
+	if different as f64 > allowed {
+		let output = save_failed_render(name, actual);
+		panic!(
+			"{} differs from {} in {} pixels, rendered card saved to {}",
+			name,
+			golden.display(),
+			different,
+			output.display()
+		);
+	}
+}
+
+fn save_failed_render(name: &str, actual: &RgbaImage) -> PathBuf {
+	let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/golden-failures");
+	fs::create_dir_all(&dir).unwrap();
+	let path = dir.join(format!("{}.png", name));
+	actual.save(&path).unwrap();
+	path
+}
+
+#[test]
+fn long_description() {
+	check_golden("long_description", &skill("Ether Prodigy"));
+}
+
+#[test]
+fn many_resources() {
+	check_golden("many_resources", &skill("Balthazar's Spirit"));
+}
+
+#[test]
+fn elite() {
+	check_golden("elite", &skill("Hundred Blades"));
+}
+
+#[test]
+fn no_resources() {
+	check_golden("no_resources", &skill("Signet of Capture"));
+}
+
+#[test]
+fn hidden_card() {
+	check_golden("hidden_card", &Skill::hidden());
+}
+
+#[test]
+fn plain_back() {
+	check_back("plain_back", Back::Plain);
+}
+
+#[test]
+fn profession_back() {
+	check_back("profession_back", Back::Profession(Profession::Ritualist));
+}
+
+#[test]
+fn campaign_back() {
+	check_back("campaign_back", Back::Campaign(Campaign::Nightfall));
+}
+
+#[test]
+fn wraps_descriptions_with_accented_letters() {
+	let mut skill = skill("Ether Prodigy");
+	skill.description = "Élite enchantment. Gain 6 Énergy and 30 seconds. ".repeat(4);
+	assert!(render(&skill).is_ok());
+}
+
+#[test]
+fn renders_empty_descriptions_and_titles() {
+	let mut skill = skill("Signet of Capture");
+	let icon = fixtures()
+		.join("icons")
+		.join(format!("{}.jpg", skill.file_name()));
+	skill.name = " ".to_owned();
+	skill.description = String::new();
+	assert!(card::render_card(&skill, &icon, &CardAssets::load().unwrap()).is_ok());
+}
+
+#[test]
+fn refuses_words_too_long_for_a_line() {
+	let mut skill = skill("Signet of Capture");
+	skill.description = "Choose".repeat(20);
+	assert!(render(&skill).is_err());
+}
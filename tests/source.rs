@@ -0,0 +1,235 @@
+mod common;
+
+use common::{fixtures, response, serve, Scratch};
+use guild_wars_skill_cards::download::{DownloadSettings, Downloader};
+use guild_wars_skill_cards::io;
+use guild_wars_skill_cards::source::{DataFile, HtmlDirectory, SkillSource, Wiki};
+use guild_wars_skill_cards::{Error, GameMode, Profession, Report, Resource, Skill};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+fn fetch(source: &dyn SkillSource, profession: Profession) -> Vec<Skill> {
+	let mut report = Report::default();
+	let fetched = source
+		.fetch(profession, None, &mut report)
+		.unwrap()
+		.unwrap();
+	assert!(report.is_empty(), "{}", report);
+	fetched.skills
+}
+
+fn names(skills: &[Skill]) -> Vec<&str> {
+	skills.iter().map(|s| s.name.as_str()).collect()
+}
+
+fn wiki(url: &str) -> Wiki {
+	let settings = DownloadSettings {
+		delay: Duration::from_millis(0),
+		..DownloadSettings::default()
+	};
+	Wiki::new(url, Downloader::new(settings).unwrap())
+}
+
+fn edit(page: &Path, from: &str, to: &str) {
+	let html = fs::read_to_string(page).unwrap();
+	assert!(html.contains(from));
+	fs::write(page, html.replacen(from, to, 1)).unwrap();
+}
+
+#[test]
+fn reads_saved_wiki_pages() {
+	let source = HtmlDirectory::new(fixtures().join("wiki"));
+	let skills = fetch(&source, Profession::Warrior);
+
+	assert!(names(&skills).contains(&"Hundred Blades"));
+	assert!(skills.iter().all(|s| s.profession == Profession::Warrior));
+}
+
+#[test]
+fn takes_skill_ids_from_the_skill_list() {
+	let source = HtmlDirectory::new(fixtures().join("wiki"));
+	let mut skills = fetch(&source, Profession::Warrior);
+	skills.extend(fetch(&source, Profession::Common));
+	let id = |name: &str| {
+		let skill = skills.iter().find(|s| s.variant_name() == name).unwrap();
+		skill.id
+	};
+
+	assert_eq!(id("Hundred Blades"), Some(381));
+	assert_eq!(id("Defy Pain (PvP)"), Some(2994));
+	assert_eq!(id("\"Save Yourselves!\" (Kurzick)"), Some(2891));
+	assert_eq!(id("\"Save Yourselves!\" (Luxon)"), Some(2892));
+
+	let hundred_blades = skills.iter().find(|s| s.id == Some(381)).unwrap();
+	assert_eq!(hundred_blades.file_name(), "381");
+	let mut renamed = hundred_blades.clone();
+	renamed.name = "Thousand Blades".to_owned();
+	assert!(renamed.is_same_skill(hundred_blades));
+}
+
+#[test]
+fn reads_json_files_like_our_cache() {
+	let source = DataFile::new(fixtures().join("cards/skills.json"));
+	assert_eq!(
+		names(&fetch(&source, Profession::Monk)),
+		vec!["Balthazar's Spirit"]
+	);
+}
+
+#[test]
+fn reads_csv_files() {
+	let source = DataFile::new(fixtures().join("skills.csv"));
+
+	let necromancer = fetch(&source, Profession::Necromancer);
+	assert_eq!(
+		necromancer[0].resources,
+		vec![
+			Resource::Sacrifice(33),
+			Resource::Energy(5),
+			Resource::Cast(0.25),
+			Resource::Recharge(2),
+		]
+	);
+
+	let warrior = fetch(&source, Profession::Warrior);
+	assert_eq!(names(&warrior), vec!["Hundred Blades", "Defy Pain"]);
+	assert!(warrior[0].is_elite);
+	assert!(warrior[0].resources.is_empty());
+	assert!(warrior[1].is_quest_reward());
+	assert_eq!(warrior[1].split_by_game_mode, Some(GameMode::PvE));
+	assert_eq!(warrior[1].type_line(), "Warrior Skill (Tactics)");
+}
+
+#[test]
+fn reads_data_files_only_once() {
+	let scratch = Scratch::new("source-data-file");
+	let path = scratch.path().join("skills.json");
+	fs::copy(fixtures().join("cards/skills.json"), &path).unwrap();
+	let source = DataFile::new(&path);
+
+	assert_eq!(
+		names(&fetch(&source, Profession::Monk)),
+		vec!["Balthazar's Spirit"]
+	);
+	fs::remove_file(&path).unwrap();
+	assert_eq!(
+		names(&fetch(&source, Profession::Warrior)),
+		vec!["Hundred Blades"]
+	);
+}
+
+#[test]
+fn rejects_unknown_file_types() {
+	let source = DataFile::new(fixtures().join("wiki/List_of_warrior_skills.html"));
+	let result = source.fetch(Profession::Warrior, None, &mut Report::default());
+	assert!(matches!(result, Err(Error::Parse(_))));
+}
+
+#[test]
+fn asks_the_wiki_whether_a_page_changed() {
+	let page = fs::read_to_string(fixtures().join("wiki/List_of_ranger_skills.html")).unwrap();
+	let ids = fixtures().join("wiki/Skill_template_format_Skill_list.html");
+	let (url, requests) = serve(vec![
+		response(
+			"200 OK",
+			&["Content-Type: text/html", "ETag: \"v1\""],
+			&page,
+		),
+		response(
+			"200 OK",
+			&["Content-Type: text/html"],
+			&fs::read_to_string(ids).unwrap(),
+		),
+		response("304 Not Modified", &["ETag: \"v1\""], ""),
+	]);
+	let wiki = wiki(&url);
+	let mut report = Report::default();
+
+	let fetched = wiki
+		.fetch(Profession::Ranger, None, &mut report)
+		.unwrap()
+		.unwrap();
+	assert!(names(&fetched.skills).contains(&"Troll Unguent"));
+	let codex = fetched
+		.skills
+		.iter()
+		.find(|s| s.variant_name() == "Charm Animal (Codex)");
+	assert_eq!(codex.unwrap().id, Some(3069));
+	assert_eq!(fetched.meta.etag.as_deref(), Some("\"v1\""));
+	assert_eq!(
+		fetched.meta.url,
+		format!("{}/wiki/List_of_ranger_skills", url)
+	);
+
+	let again = wiki.fetch(Profession::Ranger, Some(&fetched.meta), &mut report);
+	assert!(again.unwrap().is_none());
+
+	let requests = requests.lock().unwrap();
+	assert!(requests[0].starts_with("GET /wiki/List_of_ranger_skills "));
+	assert!(requests[1].starts_with("GET /wiki/Skill_template_format/Skill_list "));
+	assert!(requests[2].contains("if-none-match: \"v1\""));
+}
+
+#[test]
+fn keeps_the_skills_when_the_skill_ids_are_missing() {
+	let page = fs::read_to_string(fixtures().join("wiki/List_of_ranger_skills.html")).unwrap();
+	let warrior = fs::read_to_string(fixtures().join("wiki/List_of_warrior_skills.html")).unwrap();
+	let (url, requests) = serve(vec![
+		response("200 OK", &["Content-Type: text/html"], &page),
+		response("404 Not Found", &[], ""),
+		response("200 OK", &["Content-Type: text/html"], &warrior),
+	]);
+	let wiki = wiki(&url);
+	let mut report = Report::default();
+
+	let ranger = wiki
+		.fetch(Profession::Ranger, None, &mut report)
+		.unwrap()
+		.unwrap();
+	assert!(names(&ranger.skills).contains(&"Troll Unguent"));
+	assert!(ranger.skills.iter().all(|s| s.id.is_none()));
+
+	// We don't ask again for every profession, or report it more than once.
+	let warrior = wiki
+		.fetch(Profession::Warrior, None, &mut report)
+		.unwrap()
+		.unwrap();
+	assert!(names(&warrior.skills).contains(&"Hundred Blades"));
+	assert_eq!(requests.lock().unwrap().len(), 3);
+	assert_eq!(report.failures.len(), 1);
+	assert_eq!(report.failures[0].0, "Skill_template_format/Skill_list");
+}
+
+#[test]
+fn refreshes_only_what_changed() {
+	let scratch = Scratch::new("source-refresh");
+	let pages = scratch.wiki_pages();
+	let dirs = scratch.directories();
+	let source = HtmlDirectory::new(&pages);
+	let mut report = Report::default();
+
+	io::build_data_cache(&dirs, &source, Profession::Warrior, false, &mut report).unwrap();
+	let meta = io::load_cache_meta(&dirs, Profession::Warrior)
+		.unwrap()
+		.unwrap();
+	assert!(meta.url.ends_with("List_of_warrior_skills.html"));
+
+	let update = io::refresh_data_cache(&dirs, &source, Profession::Warrior, &mut report).unwrap();
+	assert!(update.is_empty());
+
+	edit(
+		&pages.join("List_of_warrior_skills.html"),
+		"You gain 82...154 Health.",
+		"You gain 100...200 Health.",
+	);
+	let update = io::refresh_data_cache(&dirs, &source, Profession::Warrior, &mut report).unwrap();
+	assert_eq!(names(&update.changed), vec!["Healing Signet"]);
+	assert_eq!(update.diff.changed[0].skill, "Healing Signet");
+
+	let cached = io::load_skill_cache(&dirs, Profession::Warrior).unwrap();
+	assert!(cached
+		.iter()
+		.any(|s| s.description.starts_with("You gain 100...200 Health.")));
+	assert!(report.is_empty(), "{}", report);
+}
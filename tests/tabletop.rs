@@ -0,0 +1,310 @@
+mod common;
+
+use common::{blank_cards, fixtures, skills, Scratch};
+use guild_wars_skill_cards::assets::CardAssets;
+use guild_wars_skill_cards::back::{Back, BackDesign, BackStyle};
+use guild_wars_skill_cards::io::Directories;
+use guild_wars_skill_cards::tabletop::{self, group_skills, CardOrder, Grouping, SheetLayout};
+use guild_wars_skill_cards::{Campaign, Error, GameMode, Profession, Report, Skill};
+use serde_json::Value;
+use std::fs;
+
+/// A cards directory with a blank card for each of `skills` and the hidden card.
+fn cards(scratch: &Scratch, skills: &[Skill]) -> Directories {
+	let dirs = scratch.directories();
+	blank_cards(&dirs, skills);
+	blank_cards(&dirs, &[Skill::hidden()]);
+	dirs
+}
+
+fn saved_object(dirs: &Directories, name: &str) -> Value {
+	let path = dirs.decks().join(format!("{}.json", name));
+	serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn writes_a_saved_object_for_the_deck_sheets() {
+	let skills = skills();
+	let scratch = Scratch::new("tabletop-deck");
+	let dirs = cards(&scratch, &skills);
+	let mut report = Report::default();
+	tabletop::create_tabletop_simulator_decks(
+		&skills,
+		&dirs,
+		&SheetLayout::default(),
+		&BackDesign::default(),
+		&mut report,
+	)
+	.unwrap();
+	assert!(report.is_empty(), "{}", report);
+
+	let saved = saved_object(&dirs, "Deck");
+	let deck = &saved["ObjectStates"][0];
+	assert_eq!(deck["Name"], "DeckCustom");
+	assert_eq!(deck["DeckIDs"], serde_json::json!([100, 101, 102, 103]));
+
+	let sheet = &deck["CustomDeck"]["1"];
+	assert_eq!(sheet["NumWidth"], 10);
+	assert_eq!(sheet["NumHeight"], 7);
+	let face = sheet["FaceURL"].as_str().unwrap();
+	assert!(face.starts_with("file:///"));
+	assert!(face.ends_with("Deck 1.png"));
+	assert!(sheet["BackURL"]
+		.as_str()
+		.unwrap()
+		.ends_with("decks/Back.png"));
+	assert!(dirs.decks().join("Back.png").exists());
+
+	// cards are sorted by ID, and the fixtures have none, so they keep their order
+	let cards = deck["ContainedObjects"].as_array().unwrap();
+	assert_eq!(cards.len(), 4);
+	assert_eq!(cards[2]["Name"], "Card");
+	assert_eq!(cards[2]["CardID"], 102);
+	assert_eq!(cards[2]["Nickname"], "Hundred Blades");
+	assert_eq!(
+		cards[2]["Description"],
+		"Elite Warrior Sword Attack (Swordsmanship)"
+	);
+}
+
+#[test]
+fn saves_a_lone_card_instead_of_a_deck_of_one() {
+	let skills = skills();
+	let scratch = Scratch::new("tabletop-hand");
+	let dirs = cards(&scratch, &skills);
+	let hand = vec![&skills[0]];
+	let mut report = Report::default();
+	let backs = BackDesign {
+		style: BackStyle::Profession,
+		image: None,
+	};
+	tabletop::create_hand_sheet(
+		&hand,
+		"Hand",
+		&dirs,
+		&SheetLayout::default(),
+		&backs,
+		&mut report,
+	)
+	.unwrap();
+
+	let saved = saved_object(&dirs, "Hand");
+	let card = &saved["ObjectStates"][0];
+	assert_eq!(card["Name"], "Card");
+	assert_eq!(card["CardID"], 100);
+	assert_eq!(card["Nickname"], skills[0].name.as_str());
+	assert!(card["CustomDeck"]["1"]["FaceURL"]
+		.as_str()
+		.unwrap()
+		.ends_with("Hand.png"));
+	let back = format!("{} Back.png", skills[0].profession);
+	assert!(card["CustomDeck"]["1"]["BackURL"]
+		.as_str()
+		.unwrap()
+		.ends_with(&back));
+}
+
+#[test]
+fn names_cards_after_their_variant() {
+	let mut pvp = skills().remove(0);
+	pvp.split_by_game_mode = Some(GameMode::PvP);
+	let scratch = Scratch::new("tabletop-variant");
+	let dirs = cards(&scratch, &[pvp.clone()]);
+	let mut report = Report::default();
+	tabletop::create_hand_sheet(
+		&[&pvp],
+		"Hand",
+		&dirs,
+		&SheetLayout::default(),
+		&BackDesign::default(),
+		&mut report,
+	)
+	.unwrap();
+
+	let saved = saved_object(&dirs, "Hand");
+	assert_eq!(
+		saved["ObjectStates"][0]["Nickname"],
+		format!("{} (PvP)", pvp.name)
+	);
+}
+
+#[test]
+fn picks_the_back_that_fits_the_whole_deck() {
+	let skills = skills();
+	let all: Vec<&Skill> = skills.iter().collect();
+	let warrior: Vec<&Skill> = skills
+		.iter()
+		.filter(|skill| skill.profession == Profession::Warrior)
+		.collect();
+	let design = |style| BackDesign { style, image: None };
+
+	assert_eq!(design(BackStyle::Plain).back_for(&warrior), Back::Plain);
+	assert_eq!(
+		design(BackStyle::Profession).back_for(&warrior),
+		Back::Profession(Profession::Warrior)
+	);
+	assert_eq!(
+		design(BackStyle::Campaign).back_for(&warrior),
+		Back::Campaign(Campaign::Prophecies)
+	);
+	// the fixtures mix professions
+	assert_eq!(design(BackStyle::Profession).back_for(&all), Back::Plain);
+
+	let custom = BackDesign {
+		image: Some("my back.png".into()),
+		..BackDesign::default()
+	};
+	assert_eq!(custom.back_for(&all), Back::Custom);
+}
+
+#[test]
+fn loads_back_designs_from_toml() {
+	let design: BackDesign = toml::from_str("style = \"campaign\"").unwrap();
+	assert_eq!(design.style, BackStyle::Campaign);
+	assert!(toml::from_str::<BackDesign>("style = \"fancy\"").is_err());
+}
+
+#[test]
+fn finds_custom_backs_next_to_their_design() {
+	let design = BackDesign::load(&fixtures().join("custom_back.toml")).unwrap();
+	assert_eq!(
+		design.image,
+		Some(fixtures().join("cards/icons/Hundred Blades.jpg"))
+	);
+	let scratch = Scratch::new("tabletop-custom-back");
+	let path = design
+		.save(Back::Custom, scratch.path(), &CardAssets::load().unwrap())
+		.unwrap();
+	// doesn't overwrite the plain back other decks may use
+	assert_ne!(path, scratch.path().join(Back::Plain.file_name()));
+	assert!(path.exists());
+}
+
+#[test]
+fn lays_out_sheets_like_the_layout_says() {
+	let layout = SheetLayout::load(&fixtures().join("sheet_layout.toml")).unwrap();
+	assert_eq!(layout.cards_per_sheet(), 4);
+	let skills = skills();
+	let scratch = Scratch::new("tabletop-layout");
+	let dirs = cards(&scratch, &skills);
+	let mut report = Report::default();
+	tabletop::create_tabletop_simulator_decks(
+		&skills,
+		&dirs,
+		&layout,
+		&BackDesign::default(),
+		&mut report,
+	)
+	.unwrap();
+
+	// all four cards fit on one sheet without a hidden card, scaled to the layout's size
+	let sheet = image::open(dirs.decks().join("Deck 1.png"))
+		.unwrap()
+		.into_rgba8();
+	assert_eq!(sheet.dimensions(), (300, 432));
+	assert!(!dirs.decks().join("Deck 2.png").exists());
+
+	let saved = saved_object(&dirs, "Deck");
+	let sheet = &saved["ObjectStates"][0]["CustomDeck"]["1"];
+	assert_eq!(sheet["NumWidth"], 2);
+	assert_eq!(sheet["NumHeight"], 2);
+	assert_eq!(sheet["BackIsHidden"], true);
+}
+
+#[test]
+fn rejects_layouts_tabletop_simulator_cant_load() {
+	let invalid = |layout: SheetLayout| matches!(layout.validate(), Err(Error::Parse(_)));
+	assert!(SheetLayout::default().validate().is_ok());
+	assert!(invalid(SheetLayout {
+		columns: 11,
+		..SheetLayout::default()
+	}));
+	assert!(invalid(SheetLayout {
+		rows: 1,
+		..SheetLayout::default()
+	}));
+	// 10 cards of 1200 pixels make a sheet wider than 10000 pixels
+	assert!(invalid(SheetLayout {
+		card_width: 1200,
+		..SheetLayout::default()
+	}));
+	assert!(invalid(SheetLayout {
+		card_height: 0,
+		..SheetLayout::default()
+	}));
+}
+
+#[test]
+fn refuses_more_cards_than_a_sheet_has_room_for() {
+	let layout = SheetLayout::load(&fixtures().join("sheet_layout.toml")).unwrap();
+	let card = image::RgbaImage::new(30, 43);
+	let cards = vec![card.clone(); 4];
+	assert!(tabletop::assemble_sheet(&cards, None, &layout).is_ok());
+	assert!(matches!(
+		tabletop::assemble_sheet(&cards, Some(&card), &layout),
+		Err(Error::Parse(_))
+	));
+}
+
+fn deck_names(skills: &[Skill], grouping: Grouping) -> Vec<(String, usize)> {
+	group_skills(skills, grouping)
+		.into_iter()
+		.map(|(name, deck)| (name, deck.len()))
+		.collect()
+}
+
+#[test]
+fn names_decks_after_their_group() {
+	let skills = skills();
+	let deck = |name: &str, size| (name.to_owned(), size);
+	assert_eq!(deck_names(&skills, Grouping::All), vec![deck("Deck", 4)]);
+	assert_eq!(
+		deck_names(&skills, Grouping::Campaign),
+		vec![
+			deck("Factions Deck", 1),
+			deck("Prophecies Deck", 2),
+			deck("Core Deck", 1)
+		]
+	);
+	assert_eq!(
+		deck_names(&skills, Grouping::Attribute)[3],
+		deck("No Attribute Deck", 1)
+	);
+	assert_eq!(
+		deck_names(&skills, Grouping::Elites),
+		vec![deck("Elite Deck", 2)]
+	);
+	assert_eq!("Profession".parse(), Ok(Grouping::Profession));
+	assert!("colour".parse::<Grouping>().is_err());
+}
+
+#[test]
+fn writes_a_deck_per_group_in_the_chosen_order() {
+	let skills = skills();
+	let scratch = Scratch::new("tabletop-grouped");
+	let dirs = cards(&scratch, &skills);
+	let mut report = Report::default();
+	tabletop::create_grouped_decks(
+		&skills,
+		Grouping::Campaign,
+		CardOrder::Name,
+		&dirs,
+		&SheetLayout::load(&fixtures().join("sheet_layout.toml")).unwrap(),
+		&BackDesign::default(),
+		&mut report,
+	)
+	.unwrap();
+
+	assert!(dirs.decks().join("Core Deck 1.png").exists());
+	assert!(dirs.decks().join("Factions Deck 1.png").exists());
+	assert!(!dirs.decks().join("Deck 1.png").exists());
+	let saved = saved_object(&dirs, "Prophecies Deck");
+	let cards = saved["ObjectStates"][0]["ContainedObjects"]
+		.as_array()
+		.unwrap();
+	let names: Vec<&str> = cards
+		.iter()
+		.map(|card| card["Nickname"].as_str().unwrap())
+		.collect();
+	assert_eq!(names, vec!["Balthazar's Spirit", "Hundred Blades"]);
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use guild_wars_skill_cards::template::Template;
+use guild_wars_skill_cards::{Attribute, Error, Profession, Skill};
+
+/// Warrior/Monk with 12 Swordsmanship and 9 Tactics, running Hundred Blades and Healing Signet.
+const WARRIOR_MONK: &str = "OQMSQ5aG9NAAAAAAAAAA";
+
+fn skills() -> Vec<Skill> {
+	let mut skills = common::skills();
+	for (skill, id) in skills.iter_mut().zip(&[253, 1238, 381, 1]) {
+		skill.id = Some(*id);
+	}
+	skills
+}
+
+fn skill(name: &str) -> Skill {
+	skills().into_iter().find(|s| s.name == name).unwrap()
+}
+
+#[test]
+fn decodes_template_codes() {
+	let template = Template::decode(WARRIOR_MONK).unwrap();
+
+	assert_eq!(template.primary, Profession::Warrior);
+	assert_eq!(template.secondary, Some(Profession::Monk));
+	assert_eq!(
+		template.attributes,
+		vec![(Attribute::Swordsmanship, 12), (Attribute::Tactics, 9)]
+	);
+	assert_eq!(
+		template.skills,
+		[Some(381), Some(1), None, None, None, None, None, None]
+	);
+}
+
+#[test]
+fn decodes_codes_without_the_type_header() {
+	let template = Template::decode("AGEAzQAAAAAAAAAAA").unwrap();
+
+	assert_eq!(template.primary, Profession::Elementalist);
+	assert_eq!(template.secondary, None);
+	assert_eq!(template.attributes, vec![(Attribute::EnergyStorage, 12)]);
+	assert_eq!(template.skills[0], Some(1));
+}
+
+#[test]
+fn encodes_what_it_decodes() {
+	let template = Template::decode(WARRIOR_MONK).unwrap();
+	assert_eq!(template.encode().unwrap(), WARRIOR_MONK);
+}
+
+#[test]
+fn rejects_broken_codes() {
+	assert!(matches!(Template::decode("OQ!"), Err(Error::Template(_))));
+	assert!(matches!(Template::decode("OQMS"), Err(Error::Template(_))));
+	assert!(matches!(
+		Template::decode("PQMSQ5aG9NAAAAAAAAAA"),
+		Err(Error::Template(_))
+	));
+}
+
+#[test]
+fn turns_a_hand_into_a_template_and_back() {
+	let hand = vec![
+		skill("Balthazar's Spirit"),
+		skill("Hundred Blades"),
+		skill("Signet of Capture"),
+	];
+	let template = Template::from_hand(&hand).unwrap();
+
+	// the elite skill decides the primary profession
+	assert_eq!(template.primary, Profession::Warrior);
+	assert_eq!(template.secondary, Some(Profession::Monk));
+
+	let code = template.encode().unwrap();
+	let pool = skills();
+	let names: Vec<&str> = Template::decode(&code)
+		.unwrap()
+		.skills_in(&pool)
+		.unwrap()
+		.iter()
+		.map(|s| s.name.as_str())
+		.collect();
+	assert_eq!(
+		names,
+		vec!["Balthazar's Spirit", "Hundred Blades", "Signet of Capture"]
+	);
+}
+
+#[test]
+fn refuses_hands_that_make_no_build() {
+	let three_professions = vec![
+		skill("Ether Prodigy"),
+		skill("Balthazar's Spirit"),
+		skill("Hundred Blades"),
+	];
+	assert!(Template::from_hand(&three_professions).is_err());
+
+	let too_many = vec![skill("Balthazar's Spirit"); 9];
+	assert!(Template::from_hand(&too_many).is_err());
+
+	let mut no_id = skill("Hundred Blades");
+	no_id.id = None;
+	assert!(Template::from_hand(&[no_id]).is_err());
+}
+
+#[test]
+fn complains_about_skills_missing_from_the_pool() {
+	let template = Template::decode(WARRIOR_MONK).unwrap();
+	let pool = vec![skill("Hundred Blades")];
+	assert!(matches!(template.skills_in(&pool), Err(Error::Template(_))));
+}